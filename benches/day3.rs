@@ -0,0 +1,122 @@
+use advent_of_code_2022::day3;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const ITEMS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Small xorshift generator, seeded with a constant in `generate_rucksacks`.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    fn pick(&mut self, items: &[char]) -> char {
+        items[self.next(items.len())]
+    }
+}
+
+/// Generates groups of 3 rucksacks where each rucksack has exactly one item in both halves and
+/// each group shares exactly one badge.
+fn generate_rucksacks(groups: usize, half_size: usize) -> String {
+    let mut rng = Xorshift(0x2022_0003);
+    let mut input = String::new();
+
+    for _ in 0..groups {
+        let mut items: Vec<char> = ITEMS.chars().collect();
+        let badge = items.remove(rng.next(items.len()));
+        for _ in 0..items.len() {
+            let (a, b) = (rng.next(items.len()), rng.next(items.len()));
+            items.swap(a, b);
+        }
+
+        // 51 remaining items, 17 for each rucksack so the badge is the only one shared
+        for pool in items.chunks(17) {
+            let (duplicated, pool) = pool.split_first().unwrap_or((&badge, &[]));
+            let (left_pool, right_pool) = pool.split_at(pool.len() / 2);
+
+            let mut left: Vec<char> = (0..half_size).map(|_| rng.pick(left_pool)).collect();
+            let mut right: Vec<char> = (0..half_size).map(|_| rng.pick(right_pool)).collect();
+            let duplicated_position = rng.next(half_size);
+            let badge_position = (duplicated_position + 1 + rng.next(half_size - 1)) % half_size;
+            left[duplicated_position] = *duplicated;
+            left[badge_position] = badge;
+            right[rng.next(half_size)] = *duplicated;
+
+            input.extend(left);
+            input.extend(right);
+            input.push('\n');
+        }
+    }
+
+    input
+}
+
+fn naive_part1(input: &str) -> u32 {
+    input
+        .lines()
+        .map(|line| {
+            let (left, right) = line.split_at(line.len() / 2);
+            left.chars()
+                .find(|item| right.chars().any(|other| other == *item))
+                .map_or(0, priority)
+        })
+        .sum()
+}
+
+fn naive_part2(input: &str) -> u32 {
+    let lines: Vec<&str> = input.lines().collect();
+    lines
+        .chunks(3)
+        .map(|group| {
+            group[0]
+                .chars()
+                .find(|item| {
+                    group[1].chars().any(|other| other == *item)
+                        && group[2].chars().any(|other| other == *item)
+                })
+                .map_or(0, priority)
+        })
+        .sum()
+}
+
+fn priority(item: char) -> u32 {
+    ITEMS.find(item).map_or(0, |index| index as u32 + 1)
+}
+
+fn bench_rucksacks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day3");
+    for half_size in [12, 48, 192] {
+        let input = generate_rucksacks(10_000, half_size);
+        assert_eq!(day3::part1(&input), naive_part1(&input).to_string());
+        assert_eq!(day3::part2(&input), naive_part2(&input).to_string());
+
+        group.bench_with_input(
+            BenchmarkId::new("part1/naive", half_size),
+            &input,
+            |b, i| b.iter(|| naive_part1(black_box(i))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("part1/bitset", half_size),
+            &input,
+            |b, i| b.iter(|| day3::part1(black_box(i))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("part2/naive", half_size),
+            &input,
+            |b, i| b.iter(|| naive_part2(black_box(i))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("part2/bitset", half_size),
+            &input,
+            |b, i| b.iter(|| day3::part2(black_box(i))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_rucksacks);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+use advent_of_code_2022::day4::{self, Ranges};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use regex::Regex;
+
+/// Generates pairs of assignments between sections 1 and 118, each up to 21 sections long,
+/// from a linear congruential generator with a fixed seed.
+fn generate_assignments(lines: usize) -> String {
+    let mut state: u64 = 0x2022_0004;
+    let mut next = |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+
+    let mut input = String::new();
+    for _ in 0..lines {
+        let (left_start, right_start) = (next(99) + 1, next(99) + 1);
+        let (left_end, right_end) = (left_start + next(20), right_start + next(20));
+        input.push_str(&format!(
+            "{}-{},{}-{}\n",
+            left_start, left_end, right_start, right_end
+        ));
+    }
+
+    input
+}
+
+/// The previous implementation of part 1: a regex compiled on every call and matched over the
+/// whole input.
+fn regex_part1(input: &str) -> u32 {
+    let regex = Regex::new(r"(\d+)-(\d+),(\d+)-(\d+)").unwrap();
+    regex
+        .captures_iter(input)
+        .map(|group| {
+            let sections: Vec<u32> = (1..=4)
+                .map(|i| group[i].parse::<u32>().unwrap_or_default())
+                .collect();
+            let (ls, le, rs, re) = (sections[0], sections[1], sections[2], sections[3]);
+            ((ls <= rs && le >= re) || (rs <= ls && re >= le)) as u32
+        })
+        .sum()
+}
+
+fn bench_assignments(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day4");
+    group.sample_size(10);
+
+    let input = generate_assignments(1_000_000);
+    assert_eq!(day4::part1(&input), regex_part1(&input).to_string());
+
+    group.bench_with_input(BenchmarkId::new("part1", "regex"), &input, |b, i| {
+        b.iter(|| regex_part1(black_box(i)))
+    });
+    group.bench_with_input(BenchmarkId::new("part1", "hand_written"), &input, |b, i| {
+        b.iter(|| day4::part1(black_box(i)))
+    });
+    group.bench_with_input(BenchmarkId::new("parse", "hand_written"), &input, |b, i| {
+        b.iter(|| {
+            black_box(i)
+                .lines()
+                .filter_map(|line| line.parse::<Ranges>().ok())
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_assignments);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+use advent_of_code_2022::day5::{
+    self, Crane, CrateMover9000, CrateMover9001, CrateYard, InputGenerator, Instruction,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// The previous implementation of the CrateMover 9000: one pop and one push per crate.
+struct Popping9000;
+
+impl Crane for Popping9000 {
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        for _ in 0..count {
+            if let Some(moved_crate) = from.pop() {
+                to.push(moved_crate);
+            }
+        }
+    }
+
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        self.lift(to, from, count);
+    }
+}
+
+/// The previous implementation of the CrateMover 9001: every crate popped into an intermediate
+/// stack, then popped again onto the destination.
+struct Popping9001;
+
+impl Crane for Popping9001 {
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        let mut crate_movement_queue = Vec::new();
+        for _ in 0..count {
+            if let Some(moved_crate) = from.pop() {
+                crate_movement_queue.push(moved_crate);
+            }
+        }
+        while let Some(moved_crate) = crate_movement_queue.pop() {
+            to.push(moved_crate);
+        }
+    }
+
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        self.lift(to, from, count);
+    }
+}
+
+fn follow(yard: &CrateYard, moves: &[Instruction], crane: &impl Crane) -> String {
+    let mut yard = yard.clone();
+    yard.apply_all(moves, crane).unwrap();
+    yard.top_crates()
+}
+
+fn bench_cranes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day5");
+    for max_count in [10, 1_000, 10_000] {
+        let generator = InputGenerator {
+            height: 20_000,
+            moves: 1_000,
+            max_count: Some(max_count),
+            ..InputGenerator::default()
+        };
+        let (yard, moves) = generator.generate().unwrap();
+        assert_eq!(
+            follow(&yard, &moves, &CrateMover9000),
+            follow(&yard, &moves, &Popping9000)
+        );
+        assert_eq!(
+            follow(&yard, &moves, &CrateMover9001),
+            follow(&yard, &moves, &Popping9001)
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("9000/popping", max_count),
+            &(&yard, &moves),
+            |b, (yard, moves)| b.iter(|| follow(black_box(yard), moves, &Popping9000)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("9000/drain", max_count),
+            &(&yard, &moves),
+            |b, (yard, moves)| b.iter(|| follow(black_box(yard), moves, &CrateMover9000)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("9001/popping", max_count),
+            &(&yard, &moves),
+            |b, (yard, moves)| b.iter(|| follow(black_box(yard), moves, &Popping9001)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("9001/drain", max_count),
+            &(&yard, &moves),
+            |b, (yard, moves)| b.iter(|| follow(black_box(yard), moves, &CrateMover9001)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_generated_inputs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day5/generated");
+    for (stacks, height) in [(9, 8), (90, 100), (900, 1_000)] {
+        let generator = InputGenerator {
+            stacks,
+            height,
+            moves: 10_000,
+            ..InputGenerator::default()
+        };
+        let input = generator.generate_input().unwrap();
+        assert!(day5::get_top_crates_after_moves(&input, &CrateMover9001).is_ok());
+
+        group.bench_with_input(BenchmarkId::new("part1", stacks), &input, |b, i| {
+            b.iter(|| day5::part1(black_box(i)))
+        });
+        group.bench_with_input(BenchmarkId::new("part2", stacks), &input, |b, i| {
+            b.iter(|| day5::part2(black_box(i)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_cranes, bench_generated_inputs);
+criterion_main!(benches);
@@ -1,28 +1,820 @@
 use aoc_runner_derive::aoc;
-use eyre::Result;
+use eyre::{eyre, Report, Result};
+use std::fmt;
+use std::str::FromStr;
 
-#[aoc(day2, part1)]
-pub fn part1(input: &str) -> u32 {
-    let mut total_score: u32 = 0;
-    for line in input.lines() {
-        let theirs = line.chars().nth(0);
-        let mine = line.chars().nth(2);
-        total_score += get_round_result_part1(theirs, mine);
+/// A shape that can be played in a round.
+///
+/// In the strategy guide, the opponent's column uses `A`, `B` and `C` while our column uses
+/// `X`, `Y` and `Z` (only when following part 1 semantics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+/// The result of a round, from our point of view.
+///
+/// In the strategy guide (part 2 semantics), our column uses `X` to loose, `Y` to draw and
+/// `Z` to win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 3] = [Shape::Rock, Shape::Paper, Shape::Scissors];
+
+    /// The shape defeated by this one.
+    pub fn beats(self) -> Shape {
+        match self {
+            Shape::Rock => Shape::Scissors,
+            Shape::Paper => Shape::Rock,
+            Shape::Scissors => Shape::Paper,
+        }
+    }
+
+    /// The shape that defeats this one.
+    pub fn loses_to(self) -> Shape {
+        Shape::ALL
+            .into_iter()
+            .find(|shape| shape.beats() == self)
+            .unwrap_or(self)
+    }
+
+    /// Points for choosing this shape.
+    pub fn score(self) -> u32 {
+        match self {
+            Shape::Rock => 1,
+            Shape::Paper => 2,
+            Shape::Scissors => 3,
+        }
+    }
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+
+    /// Points against the opponent.
+    pub fn score(self) -> u32 {
+        match self {
+            Outcome::Loss => 0,
+            Outcome::Draw => 3,
+            Outcome::Win => 6,
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<char> for Shape {
+    type Error = Report;
+
+    fn try_from(value: char) -> Result<Self> {
+        match value {
+            'A' | 'X' => Ok(Shape::Rock),
+            'B' | 'Y' => Ok(Shape::Paper),
+            'C' | 'Z' => Ok(Shape::Scissors),
+            _ => Err(eyre!("Unknown shape '{}'.", value)),
+        }
+    }
+}
+
+impl TryFrom<char> for Outcome {
+    type Error = Report;
+
+    fn try_from(value: char) -> Result<Self> {
+        match value {
+            'X' => Ok(Outcome::Loss),
+            'Y' => Ok(Outcome::Draw),
+            'Z' => Ok(Outcome::Win),
+            _ => Err(eyre!("Unknown outcome '{}'.", value)),
+        }
+    }
+}
+
+impl FromStr for Shape {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        single_char(s).and_then(Shape::try_from)
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        single_char(s).and_then(Outcome::try_from)
+    }
+}
+
+fn single_char(s: &str) -> Result<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(eyre!("Expected a single character, got '{}'.", s)),
+    }
+}
+
+/// A line of the strategy guide, as indices into the columns of a [`CyclicGame`].
+///
+/// `theirs` is always a shape, while `response` is either a shape or an [`Outcome`] depending on
+/// the [`Semantics`] used to read the guide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Round {
+    pub theirs: usize,
+    pub response: usize,
+}
+
+/// How to treat lines that don't contain a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Every line must be a round.
+    Strict,
+    /// Blank lines are skipped, any other line must still be a round.
+    Lenient,
+}
+
+/// How to read our column of the strategy guide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Semantics {
+    /// Our column is the shape to play (part 1).
+    Shape,
+    /// Our column is the outcome to get, `X`, `Y` or `Z` (part 2).
+    Outcome,
+}
+
+/// A shape of a [`CyclicGame`], as declared in its rule table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeRule {
+    pub name: String,
+    /// Code of the shape in the opponent's column.
+    pub theirs: char,
+    /// Code of the shape in our column, when following [`Semantics::Shape`].
+    pub mine: char,
+    pub score: u32,
+    /// Names of the shapes defeated by this one.
+    pub beats: Vec<String>,
+}
+
+/// A game where every shape beats exactly half of the other ones, like rock-paper-scissors.
+///
+/// The game is configured by a rule table, one shape per line:
+///
+/// ```text
+/// Rock A X 1 > Scissors
+/// Paper B Y 2 > Rock
+/// Scissors C Z 3 > Paper
+/// ```
+///
+/// That is the name of the shape, its code in the opponent's column, its code in our column,
+/// its score and, after the `>`, the shapes it defeats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CyclicGame {
+    shapes: Vec<ShapeRule>,
+    beats: Vec<Vec<bool>>,
+}
+
+const OUTCOME_CODES: [char; 3] = ['X', 'Y', 'Z'];
+
+const ROCK_PAPER_SCISSORS_LIZARD_SPOCK: &str = "Rock A V 1 > Scissors Lizard
+Paper B W 2 > Rock Spock
+Scissors C X 3 > Paper Lizard
+Lizard D Y 4 > Spock Paper
+Spock E Z 5 > Scissors Rock
+";
+
+impl CyclicGame {
+    /// Builds a game from its rules, checking that every pair of shapes has a winner and that
+    /// every shape beats exactly half of the other ones.
+    pub fn new(shapes: Vec<ShapeRule>) -> Result<Self> {
+        let size = shapes.len();
+        if size < 3 || size.is_multiple_of(2) {
+            return Err(eyre!(
+                "A cyclic game needs an odd number of shapes (at least 3), got {}.",
+                size
+            ));
+        }
+
+        for (index, shape) in shapes.iter().enumerate() {
+            for other in &shapes[index + 1..] {
+                if shape.name == other.name {
+                    return Err(eyre!("Shape '{}' is declared twice.", shape.name));
+                }
+                if shape.theirs == other.theirs || shape.mine == other.mine {
+                    return Err(eyre!(
+                        "Shapes '{}' and '{}' share a code.",
+                        shape.name,
+                        other.name
+                    ));
+                }
+            }
+        }
+
+        let mut beats = vec![vec![false; size]; size];
+        for (index, shape) in shapes.iter().enumerate() {
+            for beaten in &shape.beats {
+                let beaten_index = shapes
+                    .iter()
+                    .position(|other| other.name.eq(beaten))
+                    .ok_or_else(|| {
+                        eyre!("Shape '{}' beats unknown shape '{}'.", shape.name, beaten)
+                    })?;
+                if beaten_index == index {
+                    return Err(eyre!("Shape '{}' can't beat itself.", shape.name));
+                }
+                beats[index][beaten_index] = true;
+            }
+
+            let number_of_beaten = beats[index].iter().filter(|b| **b).count();
+            if number_of_beaten != size / 2 {
+                return Err(eyre!(
+                    "Shape '{}' beats {} shapes, but it should beat {}.",
+                    shape.name,
+                    number_of_beaten,
+                    size / 2
+                ));
+            }
+        }
+
+        for first in 0..size {
+            for second in first + 1..size {
+                if beats[first][second] == beats[second][first] {
+                    return Err(eyre!(
+                        "Shapes '{}' and '{}' need exactly one winner.",
+                        shapes[first].name,
+                        shapes[second].name
+                    ));
+                }
+            }
+        }
+
+        Ok(CyclicGame { shapes, beats })
+    }
+
+    /// The puzzle's game, built from the [`Shape`] rules.
+    pub fn rock_paper_scissors() -> Result<Self> {
+        let shapes = Shape::ALL
+            .into_iter()
+            .zip(['A', 'B', 'C'])
+            .zip(['X', 'Y', 'Z'])
+            .map(|((shape, theirs), mine)| ShapeRule {
+                name: shape.to_string(),
+                theirs,
+                mine,
+                score: shape.score(),
+                beats: vec![shape.beats().to_string()],
+            })
+            .collect();
+
+        Self::new(shapes)
+    }
+
+    /// Rock-paper-scissors-lizard-Spock, with codes `A` to `E` and `V` to `Z`.
+    pub fn rock_paper_scissors_lizard_spock() -> Result<Self> {
+        ROCK_PAPER_SCISSORS_LIZARD_SPOCK.parse()
+    }
+
+    pub fn shapes(&self) -> &[ShapeRule] {
+        &self.shapes
     }
 
-    total_score
+    /// Resolves a round where we play `mine` against `theirs`.
+    pub fn play(&self, mine: usize, theirs: usize) -> Outcome {
+        if self.beats[mine][theirs] {
+            Outcome::Win
+        } else if self.beats[theirs][mine] {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// The shape we have to play against `theirs` to get the `outcome`.
+    ///
+    /// With more than 3 shapes there are several candidates to win or loose, the one with the
+    /// highest score is chosen.
+    pub fn respond(&self, theirs: usize, outcome: Outcome) -> usize {
+        (0..self.shapes.len())
+            .filter(|mine| self.play(*mine, theirs) == outcome)
+            .max_by_key(|mine| (self.shapes[*mine].score, std::cmp::Reverse(*mine)))
+            .unwrap_or(theirs)
+    }
+
+    /// Parses every line of the strategy guide, failing on the first malformed one.
+    ///
+    /// A round is the opponent's code, a single space and our code. Errors point to the
+    /// 1-based line and column of the offending token.
+    pub fn parse_guide(
+        &self,
+        input: &str,
+        mode: ParseMode,
+        semantics: Semantics,
+    ) -> Result<Vec<Round>> {
+        let theirs_codes: Vec<char> = self.shapes.iter().map(|shape| shape.theirs).collect();
+        let response_codes: Vec<char> = match semantics {
+            Semantics::Shape => self.shapes.iter().map(|shape| shape.mine).collect(),
+            Semantics::Outcome => OUTCOME_CODES.to_vec(),
+        };
+
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| mode == ParseMode::Strict || !line.trim().is_empty())
+            .map(|(index, line)| parse_round(line, index + 1, &theirs_codes, &response_codes))
+            .collect()
+    }
+
+    /// Score of a single round.
+    pub fn round_score(&self, round: &Round, semantics: Semantics) -> u32 {
+        match semantics {
+            Semantics::Shape => get_round_result_part1(self, round),
+            Semantics::Outcome => get_round_result_part2(self, round),
+        }
+    }
+
+    /// Score of the whole strategy guide.
+    pub fn total_score(&self, input: &str, mode: ParseMode, semantics: Semantics) -> Result<u32> {
+        let rounds = self.parse_guide(input, mode, semantics)?;
+        Ok(rounds
+            .iter()
+            .map(|round| self.round_score(round, semantics))
+            .sum())
+    }
+}
+
+impl FromStr for CyclicGame {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let shapes = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                parse_shape_rule(line).map_err(|e| eyre!("Rule table line {}: {}", index + 1, e))
+            })
+            .collect::<Result<Vec<ShapeRule>>>()?;
+
+        Self::new(shapes)
+    }
+}
+
+fn parse_shape_rule(line: &str) -> Result<ShapeRule> {
+    let (shape, beats) = line
+        .split_once('>')
+        .ok_or_else(|| eyre!("Missing '>' before the beaten shapes."))?;
+
+    let tokens: Vec<&str> = shape.split_whitespace().collect();
+    match tokens.as_slice() {
+        [name, theirs, mine, score] => Ok(ShapeRule {
+            name: name.to_string(),
+            theirs: single_char(theirs)?,
+            mine: single_char(mine)?,
+            score: score.parse::<u32>()?,
+            beats: beats.split_whitespace().map(ToString::to_string).collect(),
+        }),
+        _ => Err(eyre!(
+            "Expected 'name theirs mine score', got '{}'.",
+            shape.trim()
+        )),
+    }
+}
+
+/// Total score of the strategy guide when our column is read with a given mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpretation {
+    pub semantics: Semantics,
+    /// For each code of our column, the index of the shape or [`Outcome`] it stands for.
+    pub mapping: Vec<usize>,
+    pub total: u32,
+}
+
+/// Scores of a strategy guide under every possible reading of our column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhatIfReport {
+    codes: Vec<(Semantics, Vec<char>, Vec<String>)>,
+    pub interpretations: Vec<Interpretation>,
+}
+
+impl WhatIfReport {
+    /// The interpretation with the highest total, the first one found on ties.
+    pub fn best(&self) -> Option<&Interpretation> {
+        self.interpretations
+            .iter()
+            .rev()
+            .max_by_key(|interpretation| interpretation.total)
+    }
+
+    fn describe(&self, interpretation: &Interpretation) -> String {
+        let mapping: Vec<String> = self
+            .codes
+            .iter()
+            .filter(|(semantics, _, _)| *semantics == interpretation.semantics)
+            .flat_map(|(_, codes, names)| {
+                codes
+                    .iter()
+                    .zip(&interpretation.mapping)
+                    .map(|(code, index)| format!("{}={}", code, names[*index]))
+            })
+            .collect();
+
+        format!("{:?} {}", interpretation.semantics, mapping.join(" "))
+    }
+}
+
+impl fmt::Display for WhatIfReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for interpretation in &self.interpretations {
+            writeln!(
+                f,
+                "{}: {}",
+                self.describe(interpretation),
+                interpretation.total
+            )?;
+        }
+        match self.best() {
+            Some(best) => write!(f, "Best: {}: {}", self.describe(best), best.total),
+            None => write!(f, "Best: none"),
+        }
+    }
+}
+
+impl CyclicGame {
+    /// Scores the strategy guide under every permutation of our column's codes, following both
+    /// part 1 and part 2 semantics.
+    ///
+    /// With rock-paper-scissors that is 6 mappings of `X`, `Y` and `Z` to shapes plus 6
+    /// mappings to outcomes.
+    pub fn what_if(&self, input: &str, mode: ParseMode) -> Result<WhatIfReport> {
+        let shape_names: Vec<String> = self.shapes.iter().map(|s| s.name.clone()).collect();
+        let outcome_names: Vec<String> = Outcome::ALL.iter().map(|o| format!("{:?}", o)).collect();
+        let codes = vec![
+            (
+                Semantics::Shape,
+                self.shapes.iter().map(|shape| shape.mine).collect(),
+                shape_names,
+            ),
+            (Semantics::Outcome, OUTCOME_CODES.to_vec(), outcome_names),
+        ];
+
+        let mut interpretations = Vec::new();
+        for (semantics, response_codes, _) in &codes {
+            let rounds = self.parse_guide(input, mode, *semantics)?;
+            for mapping in permutations(response_codes.len()) {
+                let total = rounds
+                    .iter()
+                    .map(|round| {
+                        let remapped = Round {
+                            theirs: round.theirs,
+                            response: mapping[round.response],
+                        };
+                        self.round_score(&remapped, *semantics)
+                    })
+                    .sum();
+                interpretations.push(Interpretation {
+                    semantics: *semantics,
+                    mapping,
+                    total,
+                });
+            }
+        }
+
+        Ok(WhatIfReport {
+            codes,
+            interpretations,
+        })
+    }
+}
+
+/// A played round of the strategy guide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub round: usize,
+    pub theirs: usize,
+    pub mine: usize,
+    pub outcome: Outcome,
+    pub score: u32,
+    pub running_total: u32,
+}
+
+/// Every round of the strategy guide, as played by a [`CyclicGame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tournament {
+    shape_names: Vec<String>,
+    pub entries: Vec<LogEntry>,
+}
+
+impl Tournament {
+    pub fn total(&self) -> u32 {
+        self.entries.last().map_or(0, |entry| entry.running_total)
+    }
+
+    /// Number of rounds with the given outcome.
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.outcome == outcome)
+            .count()
+    }
+
+    /// How many times we played each shape, in the order of the game's rule table.
+    pub fn shape_usage(&self) -> Vec<(&str, usize)> {
+        self.shape_names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let used = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.mine == index)
+                    .count();
+                (name.as_str(), used)
+            })
+            .collect()
+    }
+
+    /// Counts of outcomes and shapes played, in a single line.
+    pub fn summary(&self) -> String {
+        let usage: Vec<String> = self
+            .shape_usage()
+            .iter()
+            .map(|(name, used)| format!("{}: {}", name, used))
+            .collect();
+
+        format!(
+            "Wins: {}, Draws: {}, Losses: {}. {}. Total: {}",
+            self.count(Outcome::Win),
+            self.count(Outcome::Draw),
+            self.count(Outcome::Loss),
+            usage.join(", "),
+            self.total()
+        )
+    }
+
+    /// The log as CSV, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("round,theirs,mine,outcome,score,running_total\n");
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{:?},{},{}\n",
+                entry.round,
+                self.shape_names[entry.theirs],
+                self.shape_names[entry.mine],
+                entry.outcome,
+                entry.score,
+                entry.running_total
+            ));
+        }
+        csv
+    }
+}
+
+/// The log as a table followed by the summary.
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .shape_names
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max("Theirs".len());
+
+        writeln!(
+            f,
+            "{:>5} | {:<width$} | {:<width$} | {:<7} | {:>5} | {:>5}",
+            "Round",
+            "Theirs",
+            "Mine",
+            "Outcome",
+            "Score",
+            "Total",
+            width = width
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:>5} | {:<width$} | {:<width$} | {:<7} | {:>5} | {:>5}",
+                entry.round,
+                self.shape_names[entry.theirs],
+                self.shape_names[entry.mine],
+                format!("{:?}", entry.outcome),
+                entry.score,
+                entry.running_total,
+                width = width
+            )?;
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+impl CyclicGame {
+    /// Plays the strategy guide round by round, keeping a log of every round.
+    pub fn tournament(
+        &self,
+        input: &str,
+        mode: ParseMode,
+        semantics: Semantics,
+    ) -> Result<Tournament> {
+        let rounds = self.parse_guide(input, mode, semantics)?;
+        let mut running_total: u32 = 0;
+        let mut entries = Vec::new();
+
+        for (index, round) in rounds.iter().enumerate() {
+            let mine = match semantics {
+                Semantics::Shape => round.response,
+                Semantics::Outcome => self.respond(round.theirs, Outcome::ALL[round.response]),
+            };
+            let score = self.round_score(round, semantics);
+            running_total += score;
+            entries.push(LogEntry {
+                round: index + 1,
+                theirs: round.theirs,
+                mine,
+                outcome: self.play(mine, round.theirs),
+                score,
+                running_total,
+            });
+        }
+
+        Ok(Tournament {
+            shape_names: self.shapes.iter().map(|shape| shape.name.clone()).collect(),
+            entries,
+        })
+    }
+}
+
+#[aoc(day2, part1)]
+pub fn part1(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.total_score(input, ParseMode::Strict, Semantics::Shape));
+
+    match result {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
+    }
 }
 
 #[aoc(day2, part2)]
-pub fn part2(input: &str) -> u32 {
-    let mut total_score: u32 = 0;
-    for line in input.lines() {
-        let theirs = line.chars().nth(0);
-        let mine = line.chars().nth(2);
-        total_score += get_round_result_part2(theirs, mine);
+pub fn part2(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.total_score(input, ParseMode::Strict, Semantics::Outcome));
+
+    match result {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
     }
+}
 
-    total_score
+#[aoc(day2, part1, WhatIf)]
+pub fn part1_what_if(input: &str) -> String {
+    let result =
+        CyclicGame::rock_paper_scissors().and_then(|game| game.what_if(input, ParseMode::Strict));
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part1, Summary)]
+pub fn part1_summary(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Shape));
+
+    match result {
+        Ok(r) => r.summary(),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part2, Summary)]
+pub fn part2_summary(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Outcome));
+
+    match result {
+        Ok(r) => r.summary(),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part1, Table)]
+pub fn part1_table(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Shape));
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part1, Csv)]
+pub fn part1_csv(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Shape));
+
+    match result {
+        Ok(r) => format!("\n{}", r.to_csv()),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part2, Table)]
+pub fn part2_table(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Outcome));
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day2, part2, Csv)]
+pub fn part2_csv(input: &str) -> String {
+    let result = CyclicGame::rock_paper_scissors()
+        .and_then(|game| game.tournament(input, ParseMode::Strict, Semantics::Outcome));
+
+    match result {
+        Ok(r) => format!("\n{}", r.to_csv()),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn parse_round(
+    line: &str,
+    line_number: usize,
+    theirs_codes: &[char],
+    response_codes: &[char],
+) -> Result<Round> {
+    let mut chars = line.chars();
+
+    let found = chars.next();
+    let theirs = position_of(found, theirs_codes).ok_or_else(|| {
+        let expected = format!("opponent shape ({})", list_of_codes(theirs_codes));
+        token_error(line_number, 1, &expected, found)
+    })?;
+
+    match chars.next() {
+        Some(' ') => (),
+        found => return Err(token_error(line_number, 2, "a space", found)),
+    }
+
+    let found = chars.next();
+    let response = position_of(found, response_codes).ok_or_else(|| {
+        let expected = format!("our code ({})", list_of_codes(response_codes));
+        token_error(line_number, 3, &expected, found)
+    })?;
+
+    match chars.next() {
+        None => Ok(Round { theirs, response }),
+        found => Err(token_error(line_number, 4, "end of line", found)),
+    }
+}
+
+fn position_of(found: Option<char>, codes: &[char]) -> Option<usize> {
+    found.and_then(|c| codes.iter().position(|code| *code == c))
+}
+
+/// Formats codes as `A, B or C`.
+fn list_of_codes(codes: &[char]) -> String {
+    match codes.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => {
+            let rest: Vec<String> = rest.iter().map(ToString::to_string).collect();
+            format!("{} or {}", rest.join(", "), last)
+        }
+        None => String::new(),
+    }
+}
+
+fn token_error(line_number: usize, column: usize, expected: &str, found: Option<char>) -> Report {
+    match found {
+        Some(c) => eyre!(
+            "Line {}, column {}: expected {}, found '{}'.",
+            line_number,
+            column,
+            expected,
+            c
+        ),
+        None => eyre!(
+            "Line {}, column {}: expected {}, found end of line.",
+            line_number,
+            column,
+            expected
+        ),
+    }
 }
 
 /// Resolves a round (left => them, right => me) and sums the score in two steps:
@@ -32,7 +824,7 @@ pub fn part2(input: &str) -> u32 {
 /// - Draw: 3
 /// - Loss: 0
 ///
-/// Second step, points for choosing an option:
+/// Second step, points for choosing an option (in rock-paper-scissors):
 /// - Rock: 1
 /// - Paper: 2
 /// - Scissors: 3
@@ -40,27 +832,14 @@ pub fn part2(input: &str) -> u32 {
 /// Example:
 ///
 /// A vs Y results in a Win (6) + Paper selected (2) = 8 total points
-fn get_round_result_part1(theirs: Option<char>, mine: Option<char>) -> u32 {
-    match (theirs, mine) {
-        (Some(t), Some(m)) => match (t, m) {
-            ('A', 'X') => 3 + 1, // Rock vs Rock
-            ('A', 'Y') => 6 + 2, // Rock vs Paper
-            ('A', 'Z') => 0 + 3, // Rock vs Scissors
-            ('B', 'X') => 0 + 1, // Paper vs Rock
-            ('B', 'Y') => 3 + 2, // Paper vs Paper
-            ('B', 'Z') => 6 + 3, // Paper vs Scissors
-            ('C', 'X') => 6 + 1, // Scissors vs Rock
-            ('C', 'Y') => 0 + 2, // Scissors vs Paper
-            ('C', 'Z') => 3 + 3, // Scissors vs Scissors
-            (_, _) => 0,
-        },
-        (_, _) => 0,
-    }
+fn get_round_result_part1(game: &CyclicGame, round: &Round) -> u32 {
+    let mine = round.response;
+    game.play(mine, round.theirs).score() + game.shapes[mine].score
 }
 
 /// Retireves an opponent choice (left) and the strategy to follow (right).
 ///
-/// Left:
+/// Left (in rock-paper-scissors):
 /// A - Rock
 /// B - Paper
 /// C - Scissors
@@ -77,7 +856,7 @@ fn get_round_result_part1(theirs: Option<char>, mine: Option<char>) -> u32 {
 /// - Draw: 3
 /// - Loss: 0
 ///
-/// Second step, points for choosing an option:
+/// Second step, points for choosing an option (in rock-paper-scissors):
 /// - Rock: 1
 /// - Paper: 2
 /// - Scissors: 3
@@ -85,22 +864,28 @@ fn get_round_result_part1(theirs: Option<char>, mine: Option<char>) -> u32 {
 /// Example:
 ///
 /// A vs Y results in a Win (6) + Paper selected (2) = 8 total points
-fn get_round_result_part2(theirs: Option<char>, mine: Option<char>) -> u32 {
-    match (theirs, mine) {
-        (Some(t), Some(m)) => match (t, m) {
-            ('A', 'X') => 0 + 3, // Loose against Rock (Scissors)
-            ('A', 'Y') => 3 + 1, // Draw against Rock (Rock)
-            ('A', 'Z') => 6 + 2, // Win against Rock (Paper)
-            ('B', 'X') => 0 + 1, // Loose against Paper (Rock)
-            ('B', 'Y') => 3 + 2, // Draw against Paper (Paper)
-            ('B', 'Z') => 6 + 3, // Win against Paper (Scissors)
-            ('C', 'X') => 0 + 2, // Loose against Scissors (Paper)
-            ('C', 'Y') => 3 + 3, // Draw against Scissors (Scissors)
-            ('C', 'Z') => 6 + 1, // Win against Scissors (Rock)
-            (_, _) => 0,
-        },
-        (_, _) => 0,
+fn get_round_result_part2(game: &CyclicGame, round: &Round) -> u32 {
+    let outcome = Outcome::ALL[round.response];
+    outcome.score() + game.shapes[game.respond(round.theirs, outcome)].score
+}
+
+/// Every ordering of `0..size`, in lexicographic order.
+fn permutations(size: usize) -> Vec<Vec<usize>> {
+    let mut current: Vec<usize> = (0..size).collect();
+    let mut all = vec![current.clone()];
+
+    // Next lexicographic permutation, until the sequence is fully descending
+    while let Some(pivot) = (1..size).rev().find(|i| current[i - 1] < current[*i]) {
+        let successor = (pivot..size)
+            .rev()
+            .find(|i| current[*i] > current[pivot - 1])
+            .unwrap_or(pivot);
+        current.swap(pivot - 1, successor);
+        current[pivot..].reverse();
+        all.push(current.clone());
     }
+
+    all
 }
 
 #[cfg(test)]
@@ -109,46 +894,287 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case(None, None, 0)]
-    #[case(Some('A'), Some('Y'), 8)]
-    #[case(Some('B'), Some('X'), 1)]
-    #[case(Some('C'), Some('Z'), 6)]
+    #[case(Shape::Rock, Shape::Scissors, Shape::Paper)]
+    #[case(Shape::Paper, Shape::Rock, Shape::Scissors)]
+    #[case(Shape::Scissors, Shape::Paper, Shape::Rock)]
+    fn test_shape_rules(#[case] shape: Shape, #[case] beats: Shape, #[case] loses_to: Shape) {
+        assert_eq!(shape.beats(), beats);
+        assert_eq!(shape.loses_to(), loses_to);
+        assert_ne!(beats.beats(), shape);
+    }
+
+    #[rstest]
+    #[case("A", Shape::Rock)]
+    #[case("Y", Shape::Paper)]
+    #[case("C", Shape::Scissors)]
+    fn test_shape_from_str(#[case] input: &str, #[case] expected: Shape) {
+        assert_eq!(input.parse::<Shape>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("D")]
+    #[case("AX")]
+    fn test_shape_from_str_fail(#[case] input: &str) {
+        assert!(input.parse::<Shape>().is_err());
+        assert!(input.parse::<Outcome>().is_err());
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_rules() {
+        let rule_table = "Rock A X 1 > Scissors\nPaper B Y 2 > Rock\nScissors C Z 3 > Paper\n";
+
+        assert_eq!(
+            CyclicGame::rock_paper_scissors().unwrap(),
+            rule_table.parse().unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(0, 1, 8)]
+    #[case(1, 0, 1)]
+    #[case(2, 2, 6)]
     fn test_get_round_result_part1(
-        #[case] theirs: Option<char>,
-        #[case] mine: Option<char>,
+        #[case] theirs: usize,
+        #[case] response: usize,
         #[case] expected: u32,
     ) {
-        assert_eq!(get_round_result_part1(theirs, mine), expected);
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+
+        assert_eq!(
+            get_round_result_part1(&game, &Round { theirs, response }),
+            expected
+        );
     }
 
     #[test]
     fn test_get_total_score_part1() {
         let input_str = "A Y\nB X\nC Z\n";
 
-        let expected_result: u32 = 15;
+        let expected_result = 15.to_string();
 
         assert_eq!(part1(input_str), expected_result);
     }
 
     #[rstest]
-    #[case(None, None, 0)]
-    #[case(Some('A'), Some('Y'), 4)]
-    #[case(Some('B'), Some('X'), 1)]
-    #[case(Some('C'), Some('Z'), 7)]
+    #[case(0, 1, 4)]
+    #[case(1, 0, 1)]
+    #[case(2, 2, 7)]
     fn test_get_round_result_part2(
-        #[case] theirs: Option<char>,
-        #[case] mine: Option<char>,
+        #[case] theirs: usize,
+        #[case] response: usize,
         #[case] expected: u32,
     ) {
-        assert_eq!(get_round_result_part2(theirs, mine), expected);
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+
+        assert_eq!(
+            get_round_result_part2(&game, &Round { theirs, response }),
+            expected
+        );
     }
 
     #[test]
     fn test_get_total_score_part2() {
         let input_str = "A Y\nB X\nC Z\n";
 
-        let expected_result: u32 = 12;
+        let expected_result = 12.to_string();
 
         assert_eq!(part2(input_str), expected_result);
     }
+
+    #[rstest]
+    #[case(
+        "A Y\nB Q\n",
+        "Line 2, column 3: expected our code (X, Y or Z), found 'Q'."
+    )]
+    #[case(
+        "D Y\n",
+        "Line 1, column 1: expected opponent shape (A, B or C), found 'D'."
+    )]
+    #[case(
+        "A  Y\n",
+        "Line 1, column 3: expected our code (X, Y or Z), found ' '."
+    )]
+    #[case("A\n", "Line 1, column 2: expected a space, found end of line.")]
+    #[case("A YZ\n", "Line 1, column 4: expected end of line, found 'Z'.")]
+    #[case(
+        "A Y\n\nC Z\n",
+        "Line 2, column 1: expected opponent shape (A, B or C), found end of line."
+    )]
+    fn test_parse_guide_fail(#[case] input: &str, #[case] expected: &str) {
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+
+        let output = game
+            .parse_guide(input, ParseMode::Strict, Semantics::Shape)
+            .unwrap_err();
+
+        assert_eq!(output.to_string(), expected);
+    }
+
+    #[test]
+    fn test_parse_guide_lenient() {
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+        let input = "A Y\n\n  \nC Z\n";
+        let expected = vec![
+            Round {
+                theirs: 0,
+                response: 1,
+            },
+            Round {
+                theirs: 2,
+                response: 2,
+            },
+        ];
+
+        assert_eq!(
+            game.parse_guide(input, ParseMode::Lenient, Semantics::Shape)
+                .unwrap(),
+            expected
+        );
+        assert!(game
+            .parse_guide("A Y\n\nC W\n", ParseMode::Lenient, Semantics::Shape)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock().unwrap();
+        let input = "A Z\nD X\nE V\nB Y\n";
+
+        // Spock vaporizes Rock (6 + 5), Scissors decapitates Lizard (6 + 3),
+        // Spock vaporizes Rock (0 + 1), Lizard eats Paper (6 + 4)
+        assert_eq!(
+            game.total_score(input, ParseMode::Strict, Semantics::Shape)
+                .unwrap(),
+            11 + 9 + 1 + 10
+        );
+        assert!(game
+            .parse_guide("F X\n", ParseMode::Strict, Semantics::Shape)
+            .unwrap_err()
+            .to_string()
+            .contains("(A, B, C, D or E)"));
+    }
+
+    #[rstest]
+    #[case(0, Outcome::Win, 4)]
+    #[case(0, Outcome::Loss, 3)]
+    #[case(3, Outcome::Draw, 3)]
+    fn test_respond(#[case] theirs: usize, #[case] outcome: Outcome, #[case] expected: usize) {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock().unwrap();
+
+        assert_eq!(game.respond(theirs, outcome), expected);
+        assert_eq!(game.play(expected, theirs), outcome);
+    }
+
+    #[rstest]
+    #[case("Rock A X 1 > Scissors\nPaper B Y 2 > Rock\n")]
+    #[case("Rock A X 1 > Paper\nPaper B Y 2 > Scissors\nScissors C Z 3 > Paper\n")]
+    #[case("Rock A X 1 > Scissors\nPaper B Y 2 > Rock\nScissors C Z 3 > Well\n")]
+    #[case("Rock A X 1 > Scissors\nPaper A Y 2 > Rock\nScissors C Z 3 > Paper\n")]
+    #[case("Rock A X 1 Scissors\nPaper B Y 2 > Rock\nScissors C Z 3 > Paper\n")]
+    fn test_cyclic_game_from_str_fail(#[case] input: &str) {
+        assert!(input.parse::<CyclicGame>().is_err());
+    }
+
+    #[test]
+    fn test_cyclic_game_from_str() {
+        let input = "Rock A X 1 > Scissors\nPaper B Y 2 > Rock\nScissors C Z 3 > Paper\n";
+
+        assert_eq!(
+            input.parse::<CyclicGame>().unwrap(),
+            CyclicGame::rock_paper_scissors().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_permutations() {
+        let output = permutations(3);
+
+        assert_eq!(
+            output,
+            vec![
+                vec![0, 1, 2],
+                vec![0, 2, 1],
+                vec![1, 0, 2],
+                vec![1, 2, 0],
+                vec![2, 0, 1],
+                vec![2, 1, 0],
+            ]
+        );
+        assert_eq!(permutations(5).len(), 120);
+    }
+
+    #[test]
+    fn test_what_if() {
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+        let input = "A Y\nB X\nC Z\n";
+
+        let report = game.what_if(input, ParseMode::Strict).unwrap();
+        let totals: Vec<(Semantics, u32)> = report
+            .interpretations
+            .iter()
+            .map(|i| (i.semantics, i.total))
+            .collect();
+
+        assert_eq!(totals.len(), 12);
+        assert_eq!(totals[0], (Semantics::Shape, 15));
+        assert_eq!(totals[6], (Semantics::Outcome, 12));
+
+        // Reading X as Scissors, Y as Paper and Z as Rock wins every round
+        let best = report.best().unwrap();
+        assert_eq!(best.semantics, Semantics::Shape);
+        assert_eq!(best.mapping, vec![2, 1, 0]);
+        assert_eq!(best.total, 6 * 3 + 1 + 2 + 3);
+        assert!(report
+            .to_string()
+            .ends_with("Best: Shape X=Scissors Y=Paper Z=Rock: 24"));
+    }
+
+    #[test]
+    fn test_tournament() {
+        let game = CyclicGame::rock_paper_scissors().unwrap();
+        let input = "A Y\nB X\nC Z\n";
+
+        let tournament = game
+            .tournament(input, ParseMode::Strict, Semantics::Outcome)
+            .unwrap();
+
+        assert_eq!(
+            tournament.entries[2],
+            LogEntry {
+                round: 3,
+                theirs: 2,
+                mine: 0,
+                outcome: Outcome::Win,
+                score: 7,
+                running_total: 12,
+            }
+        );
+        assert_eq!(tournament.total(), part2(input).parse::<u32>().unwrap());
+        assert_eq!(
+            tournament.summary(),
+            "Wins: 1, Draws: 1, Losses: 1. Rock: 3, Paper: 0, Scissors: 0. Total: 12"
+        );
+        assert_eq!(
+            tournament.to_csv(),
+            "round,theirs,mine,outcome,score,running_total\n\
+             1,Rock,Rock,Draw,4,4\n\
+             2,Paper,Rock,Loss,1,5\n\
+             3,Scissors,Rock,Win,7,12\n"
+        );
+        assert_eq!(
+            tournament.to_string().lines().nth(1),
+            Some("    1 | Rock     | Rock     | Draw    |     4 |     4")
+        );
+    }
+
+    #[test]
+    fn test_tournament_runners() {
+        let input = "A Y\nB X\nC Z\n";
+
+        assert!(part1_csv(input).starts_with("\nround,theirs,mine,"));
+        assert!(part2_csv(input).ends_with("3,Scissors,Rock,Win,7,12\n"));
+        assert!(part1_table(input).ends_with("Total: 15"));
+        assert_eq!(part2_table(input).lines().count(), 1 + 1 + 3 + 1);
+    }
 }
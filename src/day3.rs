@@ -1,13 +1,115 @@
 use aoc_runner_derive::aoc;
-use eyre::{eyre, Result};
+use eyre::{eyre, Report, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 const CHAR_OFFSET: u32 = 9;
 const UPPERCASE_OFFSET: u32 = 26;
+const GROUP_SIZE: usize = 3;
+
+/// Assigns a priority to each item type.
+pub trait PriorityScheme {
+    fn priority(&self, item: char) -> Result<u32>;
+}
+
+/// The puzzle's scheme: `a` to `z` have priorities 1 to 26 and `A` to `Z` 27 to 52.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultScheme;
+
+/// `a` to `z` and `A` to `Z` both have priorities 1 to 26.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseInsensitiveScheme;
+
+/// Priorities given by the position of each item in an alphabet, starting at 1.
+///
+/// The alphabet is loaded from a string such as `"zyxwvutsrqponmlkjihgfedcba"`, whitespace
+/// is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlphabetScheme {
+    priorities: HashMap<char, u32>,
+}
+
+impl PriorityScheme for DefaultScheme {
+    fn priority(&self, item: char) -> Result<u32> {
+        get_priority_from_char(item)
+    }
+}
+
+impl PriorityScheme for CaseInsensitiveScheme {
+    fn priority(&self, item: char) -> Result<u32> {
+        get_priority_from_char(item.to_ascii_lowercase())
+    }
+}
+
+impl PriorityScheme for AlphabetScheme {
+    fn priority(&self, item: char) -> Result<u32> {
+        self.priorities
+            .get(&item)
+            .copied()
+            .ok_or_else(|| eyre!("Item {} is not in the alphabet", item))
+    }
+}
+
+impl FromStr for AlphabetScheme {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut priorities = HashMap::new();
+        for (index, item) in s.chars().filter(|c| !c.is_whitespace()).enumerate() {
+            if priorities.insert(item, index as u32 + 1).is_some() {
+                return Err(eyre!("Item {} appears twice in the alphabet", item));
+            }
+        }
+
+        Ok(AlphabetScheme { priorities })
+    }
+}
+
+/// One of the two compartments of a rucksack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compartment {
+    Left,
+    Right,
+}
+
+/// Moves `count` items of the same type from one compartment to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemMove {
+    pub item: char,
+    pub count: usize,
+    pub from: Compartment,
+    pub to: Compartment,
+}
+
+/// Item types found in both compartments of a rucksack, and how to separate them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RucksackReport {
+    pub line: usize,
+    pub duplicated: Vec<char>,
+    pub moves: Vec<ItemMove>,
+}
+
+impl RucksackReport {
+    /// Number of single items moved.
+    pub fn number_of_moves(&self) -> usize {
+        self.moves.iter().map(|item_move| item_move.count).sum()
+    }
+}
+
+impl fmt::Display for ItemMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} '{}' from {:?} to {:?}",
+            self.count, self.item, self.from, self.to
+        )
+    }
+}
 
 #[aoc(day3, part1)]
 pub fn part1(input: &str) -> String {
-    let result =
-        get_rucksack_items_part1(input).and_then(|items| get_total_priority_from_items(&items));
+    let result = get_total_misplaced_priority(input, &DefaultScheme);
 
     match result {
         Ok(r) => r.to_string(),
@@ -17,8 +119,7 @@ pub fn part1(input: &str) -> String {
 
 #[aoc(day3, part2)]
 pub fn part2(input: &str) -> String {
-    let result =
-        get_rucksack_items_part2(input).and_then(|items| get_total_priority_from_items(&items));
+    let result = get_total_badge_priority(input, GROUP_SIZE, &DefaultScheme);
 
     match result {
         Ok(r) => r.to_string(),
@@ -26,76 +127,229 @@ pub fn part2(input: &str) -> String {
     }
 }
 
+#[aoc(day3, part1, Moves)]
+pub fn part1_moves(input: &str) -> String {
+    let result = plan_reorganisation(input).map(|reports| {
+        reports
+            .iter()
+            .map(RucksackReport::number_of_moves)
+            .sum::<usize>()
+    });
+
+    match result {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Sums the priorities of the items found in both compartments of each rucksack.
+pub fn get_total_misplaced_priority(text: &str, scheme: &impl PriorityScheme) -> Result<u32> {
+    get_rucksack_items_part1(text).and_then(|items| get_total_priority_from_items(&items, scheme))
+}
+
 fn get_rucksack_items_part1(text: &str) -> Result<Vec<char>> {
     let mut rucksack_items: Vec<char> = Vec::new();
-    for line in text.lines() {
+    for (index, line) in text.lines().enumerate() {
+        validate_rucksack(line, index + 1)?;
         let (left, right) = line.split_at(line.len() / 2);
-        let common_item: Result<char> = get_common_item_in_both_halves(left, right);
+        let common_item: Result<char> = get_common_item_in_both_halves(left, right)
+            .map_err(|e| eyre!("Line {}: {}", index + 1, e));
         rucksack_items.push(common_item?);
     }
 
     Ok(rucksack_items)
 }
 
-fn get_rucksack_items_part2(text: &str) -> Result<Vec<char>> {
-    let mut rucksack_items: Vec<char> = Vec::new();
-    let mut iterator = text.lines();
-    loop {
-        let first_option = iterator.next();
-        let second_option = iterator.next();
-        let third_option = iterator.next();
-        match (first_option, second_option, third_option) {
-            (Some(first), Some(second), Some(third)) => {
-                let common_item: Result<char> =
-                    get_common_item_in_3_rucksacks(first, second, third);
-                rucksack_items.push(common_item?);
-            }
-            (_, _, _) => break,
-        }
+/// Sums the priorities of the badges, the only item shared by every rucksack of each group of
+/// `group_size` consecutive lines.
+pub fn get_total_badge_priority(
+    text: &str,
+    group_size: usize,
+    scheme: &impl PriorityScheme,
+) -> Result<u32> {
+    get_rucksack_items_part2(text, group_size)
+        .and_then(|items| get_total_priority_from_items(&items, scheme))
+}
+
+fn get_rucksack_items_part2(text: &str, group_size: usize) -> Result<Vec<char>> {
+    let rucksacks: Vec<&str> = text.lines().collect();
+    if group_size == 0 || !rucksacks.len().is_multiple_of(group_size) {
+        return Err(eyre!(
+            "Couldn't split {} rucksacks in groups of {}",
+            rucksacks.len(),
+            group_size
+        ));
     }
 
-    Ok(rucksack_items)
+    for (index, rucksack) in rucksacks.iter().enumerate() {
+        validate_rucksack(rucksack, index + 1)?;
+    }
+
+    rucksacks
+        .chunks(group_size)
+        .enumerate()
+        .map(|(index, group)| {
+            common_item_in_group(group)
+                .map_err(|e| eyre!("Group starting at line {}: {}", index * group_size + 1, e))
+        })
+        .collect()
+}
+
+/// Reports every item type found in both compartments of each rucksack, with the fewest
+/// single-item moves that leave each type in only one compartment.
+///
+/// Each duplicated type is moved out of the compartment holding fewer copies of it (out of the
+/// right one on ties), so compartments may end up with different sizes.
+pub fn plan_reorganisation(text: &str) -> Result<Vec<RucksackReport>> {
+    let mut reports = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        validate_rucksack(line, index + 1)?;
+        let (left, right) = line.split_at(line.len() / 2);
+        let duplicated =
+            get_items_from_bitmask(get_items_bitmask(left)? & get_items_bitmask(right)?);
+
+        let moves = duplicated
+            .iter()
+            .map(|item| {
+                let in_left = left.chars().filter(|c| c == item).count();
+                let in_right = right.chars().filter(|c| c == item).count();
+                if in_left < in_right {
+                    ItemMove {
+                        item: *item,
+                        count: in_left,
+                        from: Compartment::Left,
+                        to: Compartment::Right,
+                    }
+                } else {
+                    ItemMove {
+                        item: *item,
+                        count: in_right,
+                        from: Compartment::Right,
+                        to: Compartment::Left,
+                    }
+                }
+            })
+            .collect();
+
+        reports.push(RucksackReport {
+            line: index + 1,
+            duplicated,
+            moves,
+        });
+    }
+
+    Ok(reports)
+}
+
+/// Checks that the rucksack only has `a-z` and `A-Z` items and that it can be split in two
+/// compartments of the same size.
+fn validate_rucksack(line: &str, line_number: usize) -> Result<()> {
+    if let Some((column, item)) = line
+        .chars()
+        .enumerate()
+        .find(|(_, item)| !item.is_ascii_alphabetic())
+    {
+        return Err(eyre!(
+            "Line {}: invalid item '{}' at column {}",
+            line_number,
+            item,
+            column + 1
+        ));
+    }
+
+    if !line.len().is_multiple_of(2) {
+        return Err(eyre!(
+            "Line {}: odd number of items ({}) can't be split in two compartments",
+            line_number,
+            line.len()
+        ));
+    }
+
+    Ok(())
 }
 
 fn get_common_item_in_both_halves(left: &str, right: &str) -> Result<char> {
-    for char_left in left.chars() {
-        for char_right in right.chars() {
-            if char_left.eq(&char_right) {
-                return Ok(char_left);
-            }
-        }
+    let common = get_items_bitmask(left)? & get_items_bitmask(right)?;
+
+    match common.count_ones() {
+        0 => Err(eyre!(
+            "Not common character found in both halves: '{}' and '{}'",
+            left,
+            right
+        )),
+        1 => get_item_from_bitmask(common),
+        _ => Err(eyre!(
+            "Multiple common characters {:?} found in both halves: '{}' and '{}'",
+            get_items_from_bitmask(common),
+            left,
+            right
+        )),
+    }
+}
+
+/// Gets the only item carried by every rucksack of the group.
+pub fn common_item_in_group(rucksacks: &[&str]) -> Result<char> {
+    if rucksacks.is_empty() {
+        return Err(eyre!(
+            "Can't find a common item in an empty group of rucksacks"
+        ));
     }
 
-    Err(eyre!(
-        "Not common character found in both halves: '{}' and '{}'",
-        left,
-        right
-    ))
+    let mut common = u64::MAX;
+    for rucksack in rucksacks {
+        common &= get_items_bitmask(rucksack)?;
+    }
+
+    match common.count_ones() {
+        0 => Err(eyre!(
+            "Not common character found in {} rucksacks: '{}'",
+            rucksacks.len(),
+            rucksacks.join("', '")
+        )),
+        1 => get_item_from_bitmask(common),
+        _ => Err(eyre!(
+            "Multiple common characters {:?} found in {} rucksacks: '{}'",
+            get_items_from_bitmask(common),
+            rucksacks.len(),
+            rucksacks.join("', '")
+        )),
+    }
 }
 
-fn get_common_item_in_3_rucksacks(first: &str, second: &str, third: &str) -> Result<char> {
-    for char_first in first.chars() {
-        for char_second in second.chars() {
-            if char_first.eq(&char_second) {
-                for char_third in third.chars() {
-                    if char_first.eq(&char_third) {
-                        return Ok(char_first);
-                    }
-                }
-            }
-        }
+/// Represents a set of items as a bitmask, where bits 1 to 26 are `a` to `z` and bits 27 to
+/// 52 are `A` to `Z`.
+fn get_items_bitmask(items: &str) -> Result<u64> {
+    items.bytes().try_fold(0, |mask: u64, item| {
+        let bit = match item {
+            b'a'..=b'z' => item - b'a' + 1,
+            b'A'..=b'Z' => item - b'A' + 27,
+            _ => return Err(eyre!("Invalid item in '{}'", items)),
+        };
+        Ok(mask | 1 << bit)
+    })
+}
+
+/// Gets the first item of the bitmask, lowercase first.
+fn get_item_from_bitmask(mask: u64) -> Result<char> {
+    match mask.trailing_zeros() {
+        bit @ 1..=26 => Ok(char::from(b'a' + bit as u8 - 1)),
+        bit @ 27..=52 => Ok(char::from(b'A' + bit as u8 - 27)),
+        _ => Err(eyre!("No item in bitmask {:#x}", mask)),
     }
+}
 
-    Err(eyre!(
-        "Not common character found in 3 rucksacks: '{}',  '{}' and '{}'",
-        first,
-        second,
-        third,
-    ))
+/// Gets every item of the bitmask, lowercase first.
+fn get_items_from_bitmask(mut mask: u64) -> Vec<char> {
+    let mut items = Vec::new();
+    while let Ok(item) = get_item_from_bitmask(mask) {
+        items.push(item);
+        mask &= mask - 1;
+    }
+    items
 }
 
-fn get_total_priority_from_items(items: &[char]) -> Result<u32> {
-    let list_of_priorities = items.iter().map(|item| get_priority_from_char(*item));
+fn get_total_priority_from_items(items: &[char], scheme: &impl PriorityScheme) -> Result<u32> {
+    let list_of_priorities = items.iter().map(|item| scheme.priority(*item));
     let mut total: u32 = 0;
     for priority in list_of_priorities {
         total += priority?;
@@ -105,14 +359,14 @@ fn get_total_priority_from_items(items: &[char]) -> Result<u32> {
 
 fn get_priority_from_char(input_char: char) -> Result<u32> {
     match input_char.to_digit(36) {
-        Some(val) => {
+        Some(val) if input_char.is_ascii_alphabetic() => {
             if input_char.is_lowercase() {
                 Ok(val - CHAR_OFFSET)
             } else {
                 Ok(val + UPPERCASE_OFFSET - CHAR_OFFSET)
             }
         }
-        None => Err(eyre!("Couldn't convert {} char to a digit", input_char)),
+        _ => Err(eyre!("Couldn't convert {} char to a priority", input_char)),
     }
 }
 
@@ -153,16 +407,25 @@ mod tests {
     }
 
     #[rstest]
-    #[case("abcd", "xxax", "dxxa", 'a')]
-    fn test_get_common_item_in_3_rucksacks(
-        #[case] first: &str,
-        #[case] second: &str,
-        #[case] third: &str,
-        #[case] expected: char,
-    ) {
+    #[case(&["abcd", "xxax", "dxxa"], 'a')]
+    #[case(&["aa"], 'a')]
+    #[case(&["abcd", "xxax", "dxxa", "aZ", "Za"], 'a')]
+    #[case(&["dcbZ", "xxZx", "Zxxd"], 'Z')]
+    fn test_common_item_in_group(#[case] rucksacks: &[&str], #[case] expected: char) {
+        assert_eq!(common_item_in_group(rucksacks).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(&["abcd", "xxax", "dxxb"])]
+    fn test_common_item_in_group_fail(#[case] rucksacks: &[&str]) {
+        assert!(common_item_in_group(rucksacks).is_err());
+    }
+
+    #[test]
+    fn test_common_item_in_empty_group() {
         assert_eq!(
-            get_common_item_in_3_rucksacks(first, second, third).unwrap(),
-            expected
+            common_item_in_group(&[]).unwrap_err().to_string(),
+            "Can't find a common item in an empty group of rucksacks"
         );
     }
 
@@ -171,7 +434,7 @@ mod tests {
         let input_str = "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg\nwMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\nttgJtRGJQctTZtZT\nCrZsJsPPZsGzwwsLwLmpwMDw\n";
         let expected_result: Vec<char> = vec!['r', 'Z'];
 
-        let output_list = get_rucksack_items_part2(input_str).unwrap();
+        let output_list = get_rucksack_items_part2(input_str, 3).unwrap();
 
         assert_eq!(output_list.len(), expected_result.len());
 
@@ -184,6 +447,27 @@ mod tests {
         assert_eq!(matching, expected_result.len());
     }
 
+    #[rstest]
+    #[case(2, 3)]
+    #[case(3, 2)]
+    #[case(6, 1)]
+    fn test_get_rucksack_items_part2_group_size(#[case] group_size: usize, #[case] groups: usize) {
+        let input_str = "aZ\naY\naX\nWa\nVa\nUa\n";
+
+        let output = get_rucksack_items_part2(input_str, group_size);
+
+        assert_eq!(output.unwrap(), vec!['a'; groups]);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(4)]
+    fn test_get_rucksack_items_part2_fail(#[case] group_size: usize) {
+        let input_str = "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg\nwMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\nttgJtRGJQctTZtZT\nCrZsJsPPZsGzwwsLwLmpwMDw\n";
+
+        assert!(get_rucksack_items_part2(input_str, group_size).is_err());
+    }
+
     #[rstest]
     #[case('a', 1)]
     #[case('z', 26)]
@@ -197,8 +481,118 @@ mod tests {
     fn test_get_total_priority_from_items() {
         let input_priorities = vec!['p', 'L', 'P', 'v', 't', 's'];
 
-        let output_total = get_total_priority_from_items(&input_priorities);
+        let output_total = get_total_priority_from_items(&input_priorities, &DefaultScheme);
 
         assert_eq!(output_total.unwrap(), 157)
     }
+
+    #[rstest]
+    #[case("", 0)]
+    #[case("a", 1 << 1)]
+    #[case("aZa", 1 << 1 | 1 << 52)]
+    fn test_get_items_bitmask(#[case] items: &str, #[case] expected: u64) {
+        assert_eq!(get_items_bitmask(items).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("ab1")]
+    #[case("añ")]
+    fn test_get_items_bitmask_fail(#[case] items: &str) {
+        assert!(get_items_bitmask(items).is_err());
+    }
+
+    #[rstest]
+    #[case(0, vec![])]
+    #[case(1 << 1, vec!['a'])]
+    #[case(1 << 52 | 1 << 27 | 1 << 2, vec!['b', 'A', 'Z'])]
+    fn test_get_items_from_bitmask(#[case] mask: u64, #[case] expected: Vec<char>) {
+        assert_eq!(get_items_from_bitmask(mask), expected);
+    }
+
+    #[rstest]
+    #[case("abca\nab1b\n", "Line 2: invalid item '1' at column 3")]
+    #[case("abca\nabéb\n", "Line 2: invalid item 'é' at column 3")]
+    #[case(
+        "abcab\n",
+        "Line 1: odd number of items (5) can't be split in two compartments"
+    )]
+    #[case(
+        "abab\n",
+        "Line 1: Multiple common characters ['a', 'b'] found in both halves: 'ab' and 'ab'"
+    )]
+    #[case(
+        "abcd\n",
+        "Line 1: Not common character found in both halves: 'ab' and 'cd'"
+    )]
+    fn test_part1_fail(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(part1(input), expected);
+    }
+
+    #[rstest]
+    #[case("ab\nab\nab\n", "Group starting at line 1: Multiple common characters ['a', 'b'] found in 3 rucksacks: 'ab', 'ab', 'ab'")]
+    #[case("ab\nab\n1b\n", "Line 3: invalid item '1' at column 1")]
+    fn test_part2_fail(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(part2(input), expected);
+    }
+
+    #[rstest]
+    #[case('1')]
+    #[case('é')]
+    fn test_chars_fail(#[case] input_char: char) {
+        assert!(get_priority_from_char(input_char).is_err());
+    }
+
+    #[rstest]
+    #[case('a', 1)]
+    #[case('A', 1)]
+    #[case('Z', 26)]
+    fn test_case_insensitive_scheme(#[case] input_char: char, #[case] char_value: u32) {
+        assert_eq!(
+            CaseInsensitiveScheme.priority(input_char).unwrap(),
+            char_value
+        );
+    }
+
+    #[test]
+    fn test_alphabet_scheme() {
+        let scheme: AlphabetScheme = "ZYXWVUTSRQPONMLKJIHGFEDCBA\nzyxwvutsrqponmlkjihgfedcba"
+            .parse()
+            .unwrap();
+        let input_priorities = vec!['p', 'L', 'P', 'v', 't', 's'];
+
+        assert_eq!(scheme.priority('Z').unwrap(), 1);
+        assert_eq!(scheme.priority('a').unwrap(), 52);
+        assert_eq!(
+            get_total_priority_from_items(&input_priorities, &scheme).unwrap(),
+            37 + 15 + 11 + 31 + 33 + 34
+        );
+        assert!(scheme.priority('1').is_err());
+        assert!("abca".parse::<AlphabetScheme>().is_err());
+    }
+
+    #[test]
+    fn test_plan_reorganisation() {
+        let input_str = "vJrwpWtwJgWrhcsFMMfFFhFp\naabAbb\nabcd\n";
+
+        let output = plan_reorganisation(input_str).unwrap();
+
+        assert_eq!(output[0].duplicated, vec!['p']);
+        assert_eq!(
+            output[0].moves,
+            vec![ItemMove {
+                item: 'p',
+                count: 1,
+                from: Compartment::Right,
+                to: Compartment::Left,
+            }]
+        );
+        assert_eq!(output[1].duplicated, vec!['b']);
+        assert_eq!(
+            output[1].moves[0].to_string(),
+            "move 1 'b' from Left to Right"
+        );
+        assert_eq!(output[1].number_of_moves(), 1);
+        assert!(output[2].duplicated.is_empty());
+        assert_eq!(output[2].number_of_moves(), 0);
+    }
 }
@@ -1,77 +1,289 @@
+use crate::interval::{Interval, IntervalSet};
 use aoc_runner_derive::aoc;
-use eyre::Result;
-use regex::{Captures, Regex};
+use eyre::{eyre, Report, Result};
+use std::fmt;
+use std::str::FromStr;
 
-struct Ranges {
-    left_start: u32,
-    left_end: u32,
-    right_start: u32,
-    right_end: u32,
+/// Section assignments of a pair of elves, parsed from a line like `2-4,6-8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ranges {
+    pub left: Interval,
+    pub right: Interval,
+}
+
+impl Ranges {
+    /// Number of sections assigned to both elves.
+    pub fn overlap(&self) -> u64 {
+        self.left
+            .intersection(&self.right)
+            .map_or(0, |shared| shared.len())
+    }
+}
+
+/// Splits the line in place, so nothing is allocated unless the line is invalid.
+impl FromStr for Ranges {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (left, right) = s
+            .split_once(',')
+            .ok_or_else(|| eyre!("Expected two ranges separated by ',', got '{}'", s))?;
+
+        Ok(Ranges {
+            left: left.parse()?,
+            right: right.parse()?,
+        })
+    }
+}
+
+/// Section assignments of a group of any number of elves, parsed from a line like
+/// `2-4,6-8,3-5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub elves: Vec<Interval>,
+}
+
+impl FromStr for Group {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let elves = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Interval>>>()?;
+
+        Ok(Group { elves })
+    }
+}
+
+/// Sections covered by more than one elf, and the elves whose sections are all covered by
+/// other elves.
+///
+/// Elves with the same assignment are redundant with each other, so each one of them is listed
+/// even though removing all of them would leave sections uncovered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlapAnalysis {
+    pub shared: IntervalSet,
+    /// Indices of the redundant elves, in the order they were given.
+    pub redundant: Vec<usize>,
+}
+
+/// Overlap analysis of every group and of the whole crew.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrewReport {
+    pub groups: Vec<OverlapAnalysis>,
+    pub crew: OverlapAnalysis,
+    /// Line and position in the line of every elf, indexed as in the crew analysis.
+    pub elves: Vec<(usize, usize)>,
+}
+
+impl CrewReport {
+    /// Line and position in the line of the elves redundant across the whole crew.
+    pub fn crew_redundant(&self) -> Vec<(usize, usize)> {
+        self.crew
+            .redundant
+            .iter()
+            .map(|index| self.elves[*index])
+            .collect()
+    }
+}
+
+/// Finds the sections covered by more than one elf with a sweep line over the starts and ends
+/// of every assignment.
+pub fn analyse_overlaps(elves: &[Interval]) -> OverlapAnalysis {
+    // An assignment adds one elf at its start and removes it right after its end
+    let mut events: Vec<(u64, i32)> = elves
+        .iter()
+        .flat_map(|elf| [(u64::from(elf.start()), 1), (u64::from(elf.end()) + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut shared = IntervalSet::new();
+    let mut coverage: i32 = 0;
+    for (index, (position, delta)) in events.iter().enumerate() {
+        coverage += delta;
+        match events.get(index + 1) {
+            Some((next, _)) if next > position && coverage > 1 => {
+                let start = *position as u32;
+                let end = (next - 1) as u32;
+                if let Ok(interval) = Interval::new(start, end) {
+                    shared.insert(interval);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let redundant = elves
+        .iter()
+        .enumerate()
+        .filter(|(_, elf)| shared.contains(elf))
+        .map(|(index, _)| index)
+        .collect();
+
+    OverlapAnalysis { shared, redundant }
+}
+
+/// Parses every line as a [`Group`] and analyses the overlaps in each group and across the
+/// whole crew.
+pub fn analyse_crew(input: &str) -> Result<CrewReport> {
+    let mut report = CrewReport::default();
+    let mut crew: Vec<Interval> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let group = line
+            .parse::<Group>()
+            .map_err(|e| eyre!("Line {}: {}", index + 1, e))?;
+        report.groups.push(analyse_overlaps(&group.elves));
+        report
+            .elves
+            .extend((0..group.elves.len()).map(|position| (index + 1, position)));
+        crew.extend(group.elves);
+    }
+    report.crew = analyse_overlaps(&crew);
+
+    Ok(report)
+}
+
+/// Relation between the two assignments of a pair of elves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predicate {
+    /// One assignment contains the other.
+    FullContainment,
+    /// Both assignments share at least one section.
+    AnyOverlap,
+    /// Both assignments don't share any section.
+    Disjoint,
+    /// Both assignments are disjoint, with no sections in between.
+    Adjacent,
+    /// Both assignments are the same.
+    Identical,
+}
+
+impl Predicate {
+    pub fn matches(self, ranges: &Ranges) -> bool {
+        let (left, right) = (&ranges.left, &ranges.right);
+        match self {
+            Predicate::FullContainment => left.contains(right) || right.contains(left),
+            Predicate::AnyOverlap => left.overlaps(right),
+            Predicate::Disjoint => !left.overlaps(right),
+            Predicate::Adjacent => left.is_adjacent(right),
+            Predicate::Identical => left == right,
+        }
+    }
+}
+
+/// Pairs of elves matching a [`Predicate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PredicateMatches {
+    /// Line numbers of the matching pairs, starting at 1.
+    pub lines: Vec<usize>,
+}
+
+impl PredicateMatches {
+    pub fn count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// The number of matching pairs followed by their line numbers, like `2 (lines 4, 7)`.
+impl fmt::Display for PredicateMatches {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.lines.is_empty() {
+            return write!(f, "0");
+        }
+
+        let lines: Vec<String> = self.lines.iter().map(ToString::to_string).collect();
+        write!(f, "{} (lines {})", self.count(), lines.join(", "))
+    }
 }
 
 #[aoc(day4, part1)]
 pub fn part1(input: &str) -> String {
-    match compile_regex(input, get_ranges_fully_contained) {
-        Ok(r) => r.to_string(),
+    match count_ranges(input, Predicate::FullContainment) {
+        Ok(r) => r.count().to_string(),
         Err(e) => e.to_string(),
     }
 }
 
 #[aoc(day4, part2)]
 pub fn part2(input: &str) -> String {
-    match compile_regex(input, get_ranges_partially_contained) {
-        Ok(r) => r.to_string(),
+    match count_ranges(input, Predicate::AnyOverlap) {
+        Ok(r) => r.count().to_string(),
         Err(e) => e.to_string(),
     }
 }
 
-fn compile_regex(
-    input: &str,
-    ranges_contained_function: fn(Result<Ranges>) -> Result<u32>,
-) -> Result<u32> {
-    let regex = Regex::new(r"(\d+)-(\d+),(\d+)-(\d+)")?;
-    let total_ranges = regex
-        .captures_iter(input)
-        .into_iter()
-        .map(|group| group_to_ranges(&group))
-        .map(ranges_contained_function)
-        .map(|group| group.unwrap_or_default())
-        .sum();
-
-    Ok(total_ranges)
+#[aoc(day4, part1, Disjoint)]
+pub fn part1_disjoint(input: &str) -> String {
+    match count_ranges(input, Predicate::Disjoint) {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
+    }
 }
 
-fn group_to_ranges(group: &Captures) -> Result<Ranges> {
-    Ok(Ranges {
-        left_start: group[1].parse::<u32>()?,
-        left_end: group[2].parse::<u32>()?,
-        right_start: group[3].parse::<u32>()?,
-        right_end: group[4].parse::<u32>()?,
-    })
+#[aoc(day4, part1, Adjacent)]
+pub fn part1_adjacent(input: &str) -> String {
+    match count_ranges(input, Predicate::Adjacent) {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
+    }
 }
 
-fn get_ranges_fully_contained(ranges_maybe: Result<Ranges>) -> Result<u32> {
-    let ranges = ranges_maybe?;
+#[aoc(day4, part1, Identical)]
+pub fn part1_identical(input: &str) -> String {
+    match count_ranges(input, Predicate::Identical) {
+        Ok(r) => r.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
 
-    if (ranges.left_start <= ranges.right_start && ranges.left_end >= ranges.right_end)
-        || (ranges.right_start <= ranges.left_start && ranges.right_end >= ranges.left_end)
-    {
-        Ok(1)
-    } else {
-        Ok(0)
+#[aoc(day4, part2, Sections)]
+pub fn part2_sections(input: &str) -> String {
+    match get_overlap_amounts(input) {
+        Ok(r) => r.iter().sum::<u64>().to_string(),
+        Err(e) => e.to_string(),
     }
 }
 
-fn get_ranges_partially_contained(ranges_maybe: Result<Ranges>) -> Result<u32> {
-    let ranges = ranges_maybe?;
+#[aoc(day4, part2, Crew)]
+pub fn part2_crew(input: &str) -> String {
+    match analyse_crew(input) {
+        Ok(r) => format!(
+            "{} sections shared across the crew, {} elves fully redundant within their group",
+            r.crew.shared.len(),
+            r.groups.iter().map(|g| g.redundant.len()).sum::<usize>()
+        ),
+        Err(e) => e.to_string(),
+    }
+}
 
-    if (ranges.left_start <= ranges.right_start && ranges.left_end >= ranges.right_start)
-        || (ranges.right_start <= ranges.left_start && ranges.right_end >= ranges.left_start)
-    {
-        Ok(1)
-    } else {
-        Ok(0)
+/// Parses every line as a pair of [`Ranges`] and finds the ones matching the predicate.
+pub fn count_ranges(input: &str, predicate: Predicate) -> Result<PredicateMatches> {
+    let mut matches = PredicateMatches::default();
+    for (index, line) in input.lines().enumerate() {
+        let ranges = line
+            .parse::<Ranges>()
+            .map_err(|e| eyre!("Line {}: {}", index + 1, e))?;
+        if predicate.matches(&ranges) {
+            matches.lines.push(index + 1);
+        }
     }
+
+    Ok(matches)
+}
+
+/// Parses every line as a pair of [`Ranges`] and gets the number of sections they share.
+pub fn get_overlap_amounts(input: &str) -> Result<Vec<u64>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse::<Ranges>()
+                .map(|ranges| ranges.overlap())
+                .map_err(|e| eyre!("Line {}: {}", index + 1, e))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -79,6 +291,13 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
+    fn ranges(ls: u32, le: u32, rs: u32, re: u32) -> Ranges {
+        Ranges {
+            left: Interval::new(ls, le).unwrap(),
+            right: Interval::new(rs, re).unwrap(),
+        }
+    }
+
     #[test]
     fn test_part1() {
         let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n50-60,51-59\n";
@@ -90,27 +309,58 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[rstest]
+    #[case("2-4,6-8", 2, 4, 6, 8)]
+    #[case("50-60,51-59", 50, 60, 51, 59)]
+    #[case("6-6,4-6", 6, 6, 4, 6)]
+    fn test_ranges_from_str(
+        #[case] line: &str,
+        #[case] ls: u32,
+        #[case] le: u32,
+        #[case] rs: u32,
+        #[case] re: u32,
+    ) {
+        let expected = ranges(ls, le, rs, re);
+
+        assert_eq!(line.parse::<Ranges>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "2-4,6-8\n2-4;6-8\n",
+        "Line 2: Expected two ranges separated by ',', got '2-4;6-8'"
+    )]
+    #[case("2-4,6-8\n\n", "Line 2: Expected two ranges separated by ',', got ''")]
+    #[case("2-4,68\n", "Line 1: Expected a range like 'start-end', got '68'")]
+    #[case("4-2,6-8\n", "Line 1: Range '4-2' starts after it ends")]
+    #[case(
+        "2-4,6-x\n",
+        "Line 1: Invalid section 'x': invalid digit found in string"
+    )]
+    #[case(
+        "2-4,6-8,\n",
+        "Line 1: Invalid section '8,': invalid digit found in string"
+    )]
+    fn test_part1_fail(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(part1(input), expected);
+    }
+
     #[rstest]
     #[case(2, 4, 6, 8, 0)]
     #[case(2, 8, 3, 7, 1)]
     #[case(5, 10, 6, 9, 1)]
-    fn test_get_ranges_fully_contained(
+    fn test_predicate_full_containment(
         #[case] ls: u32,
         #[case] le: u32,
         #[case] rs: u32,
         #[case] re: u32,
         #[case] expected: u32,
     ) {
-        let input: Result<Ranges> = Ok(Ranges {
-            left_start: ls,
-            left_end: le,
-            right_start: rs,
-            right_end: re,
-        });
+        let input = ranges(ls, le, rs, re);
 
-        let output = get_ranges_fully_contained(input);
+        let output = Predicate::FullContainment.matches(&input);
 
-        assert_eq!(output.unwrap(), expected);
+        assert_eq!(output as u32, expected);
     }
 
     #[rstest]
@@ -121,22 +371,113 @@ mod tests {
     #[case(6, 7, 4, 6, 1)]
     #[case(2, 6, 4, 8, 1)]
     #[case(5, 10, 6, 9, 1)]
-    fn test_get_ranges_partially_contained(
+    fn test_predicate_any_overlap(
         #[case] ls: u32,
         #[case] le: u32,
         #[case] rs: u32,
         #[case] re: u32,
         #[case] expected: u32,
     ) {
-        let input: Result<Ranges> = Ok(Ranges {
-            left_start: ls,
-            left_end: le,
-            right_start: rs,
-            right_end: re,
-        });
+        let input = ranges(ls, le, rs, re);
+
+        let output = Predicate::AnyOverlap.matches(&input);
 
-        let output = get_ranges_partially_contained(input);
+        assert_eq!(output as u32, expected);
+    }
+
+    #[rstest]
+    #[case(Predicate::FullContainment, vec![4, 5, 7])]
+    #[case(Predicate::AnyOverlap, vec![3, 4, 5, 6, 7])]
+    #[case(Predicate::Disjoint, vec![1, 2])]
+    #[case(Predicate::Adjacent, vec![2])]
+    #[case(Predicate::Identical, vec![])]
+    fn test_count_ranges(#[case] predicate: Predicate, #[case] expected: Vec<usize>) {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n50-60,51-59\n";
+
+        let output = count_ranges(input, predicate).unwrap();
+
+        assert_eq!(output.count(), expected.len());
+        assert_eq!(output.lines, expected);
+    }
+
+    #[test]
+    fn test_predicate_runners() {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n";
+
+        assert_eq!(part1_disjoint(input), "2 (lines 1, 2)");
+        assert_eq!(part1_adjacent(input), "1 (lines 2)");
+        assert_eq!(part1_identical(input), "0");
+    }
+
+    #[rstest]
+    #[case(3, 5, 3, 5, true)]
+    #[case(3, 5, 3, 6, false)]
+    fn test_predicate_identical(
+        #[case] ls: u32,
+        #[case] le: u32,
+        #[case] rs: u32,
+        #[case] re: u32,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            Predicate::Identical.matches(&ranges(ls, le, rs, re)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_get_overlap_amounts() {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n";
+
+        let output = get_overlap_amounts(input).unwrap();
+
+        assert_eq!(output, vec![0, 0, 1, 5, 1, 3]);
+    }
+
+    #[test]
+    fn test_analyse_overlaps() {
+        let elves: Vec<Interval> = ["2-4", "3-9", "8-8", "12-15", "9-12", "20-21"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let output = analyse_overlaps(&elves);
+
+        let shared: Vec<String> = output
+            .shared
+            .intervals()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(shared, vec!["3-4", "8-9", "12-12"]);
+        assert_eq!(output.redundant, vec![2]);
+    }
+
+    #[test]
+    fn test_analyse_crew() {
+        let input = "2-4,3-5,4-4\n5-7\n1-1,1-1\n";
+
+        let output = analyse_crew(input).unwrap();
+
+        assert_eq!(output.groups[0].redundant, vec![2]);
+        assert!(output.groups[1].shared.is_empty());
+        assert_eq!(output.groups[2].redundant, vec![0, 1]);
+        assert_eq!(output.crew.shared.len(), 4);
+        assert_eq!(
+            output.crew_redundant(),
+            vec![(1, 1), (1, 2), (3, 0), (3, 1)]
+        );
+        assert!(analyse_crew("2-4,3-5\n5-7,\n").is_err());
+    }
+
+    #[test]
+    fn test_full_width_ranges() {
+        let input = "0-4294967295,0-4294967295\n";
 
-        assert_eq!(output.unwrap(), expected);
+        assert_eq!(part2_sections(input), "4294967296");
+        assert_eq!(
+            part2_crew(input),
+            "4294967296 sections shared across the crew, 2 elves fully redundant within their group"
+        );
     }
 }
@@ -1,17 +1,281 @@
 use aoc_runner_derive::aoc;
-use eyre::{eyre, Result};
+use eyre::{eyre, Report, Result};
 use regex::Regex;
-use std::collections::VecDeque;
-use std::string::ToString;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 const REGEX: &str = r"move (\d+) from (\d+) to (\d+)";
 
+/// First and last column of the number of each stack in the drawing.
+type StackColumns = Vec<(usize, usize)>;
+
+static INSTRUCTION_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Compiles the instruction regex the first time it's needed, and reuses it afterwards.
+fn instruction_regex() -> Result<&'static Regex> {
+    if let Some(regex) = INSTRUCTION_REGEX.get() {
+        return Ok(regex);
+    }
+
+    let regex = Regex::new(REGEX)?;
+    Ok(INSTRUCTION_REGEX.get_or_init(|| regex))
+}
+
+/// Moves crates from the top of one stack to the top of another one.
+pub trait Crane {
+    /// Moves the top `count` crates of `from` on top of `to`. `from` has at least `count`
+    /// crates.
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize);
+
+    /// Undoes a lift of `count` crates from `from` to `to`, putting them back on `from` in
+    /// their original order. `to` has at least `count` crates.
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize);
+}
+
+/// Moves one crate at a time, so the moved crates end up in reverse order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrateMover9000;
+
+/// Moves all the crates at once, so they keep their order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrateMover9001;
+
+/// Moves up to `capacity` crates at once, so each chunk of crates keeps its order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CappedCrane {
+    capacity: usize,
+}
+
+impl Crane for CrateMover9000 {
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        let start = from.len() - count;
+        to.extend(from.drain(start..).rev());
+    }
+
+    /// Reversing the crates again puts them back in order.
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        self.lift(to, from, count);
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        let start = from.len() - count;
+        to.extend(from.drain(start..));
+    }
+
+    /// The crates kept their order, so they can be lifted back.
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        self.lift(to, from, count);
+    }
+}
+
+impl CappedCrane {
+    pub fn new(capacity: usize) -> Result<Self> {
+        if capacity == 0 {
+            return Err(eyre!("A crane must be able to lift at least one crate."));
+        }
+
+        Ok(CappedCrane { capacity })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Moves all the crates at once, then puts the chunks in the order they would have been lifted:
+/// the top chunk ends up at the bottom.
+impl Crane for CappedCrane {
+    fn lift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        let bottom = to.len();
+        CrateMover9001.lift(from, to, count);
+
+        let moved = &mut to[bottom..];
+        moved.reverse();
+        for chunk in moved.chunks_mut(self.capacity) {
+            chunk.reverse();
+        }
+    }
+
+    /// Lifting the crates back would split them in different chunks when the number of crates
+    /// isn't a multiple of the capacity, so the chunks are put back in order from the top.
+    fn unlift<T>(&self, from: &mut Vec<T>, to: &mut Vec<T>, count: usize) {
+        let bottom = from.len();
+        CrateMover9001.lift(to, from, count);
+
+        let moved = &mut from[bottom..];
+        moved.reverse();
+        for chunk in moved.rchunks_mut(self.capacity) {
+            chunk.reverse();
+        }
+    }
+}
+
+/// A `move n from a to b` instruction. Stacks are numbered from 0, unlike in the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl FromStr for Move {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let groups = instruction_regex()?
+            .captures(s)
+            .ok_or_else(|| eyre!("Couldn't parse instruction groups."))?;
+
+        let stack_index = |number: &str| {
+            number
+                .parse::<usize>()?
+                .checked_sub(1)
+                .ok_or_else(|| eyre!("Stacks are numbered from 1, found stack 0."))
+        };
+
+        Ok(Move {
+            count: groups[1].parse::<usize>()?,
+            from: stack_index(&groups[2])?,
+            to: stack_index(&groups[3])?,
+        })
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+/// A move read from the puzzle input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    /// Line of the move in the input, starting at 1.
+    pub line: usize,
+    pub movement: Move,
+}
+
+/// Stacks of crates, each one listed from the bottom to the top.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CrateYard {
+    stacks: Vec<Vec<char>>,
+}
+
+impl CrateYard {
+    pub fn new(stacks: Vec<Vec<char>>) -> Self {
+        CrateYard { stacks }
+    }
+
+    pub fn stacks(&self) -> &[Vec<char>] {
+        &self.stacks
+    }
+
+    /// Moves crates as told by the instruction, with the given crane.
+    pub fn apply(&mut self, instruction: &Move, crane: &impl Crane) -> Result<()> {
+        apply_move(&mut self.stacks, instruction, crane)
+    }
+
+    /// Follows every instruction in order. Errors give the input line of the failing
+    /// instruction.
+    pub fn apply_all(&mut self, instructions: &[Instruction], crane: &impl Crane) -> Result<()> {
+        for instruction in instructions {
+            self.apply(&instruction.movement, crane)
+                .map_err(|e| instruction_error(instruction, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves crates back as they were before the instruction was followed with the given crane.
+    pub fn undo(&mut self, instruction: &Move, crane: &impl Crane) -> Result<()> {
+        undo_move(&mut self.stacks, instruction, crane)
+    }
+
+    /// Undoes every instruction, from the last one to the first one. Errors give the input line
+    /// of the failing instruction.
+    pub fn undo_all(&mut self, instructions: &[Instruction], crane: &impl Crane) -> Result<()> {
+        for instruction in instructions.iter().rev() {
+            self.undo(&instruction.movement, crane)
+                .map_err(|e| instruction_error(instruction, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// The crate on top of each stack, skipping the empty ones.
+    pub fn top_crates(&self) -> String {
+        self.stacks
+            .iter()
+            .filter_map(|stack| stack.last())
+            .collect()
+    }
+}
+
+/// Draws the stacks like the puzzle does, with every line padded to the width of the yard and
+/// without a line break after the stack numbers.
+impl fmt::Display for CrateYard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            for (index, stack) in self.stacks.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " ")?;
+                }
+                match stack.get(level) {
+                    Some(c) => write!(f, "[{}]", c)?,
+                    None => write!(f, "   ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for number in 1..=self.stacks.len() {
+            if number > 1 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:^3}", number)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the drawing of the stacks, including the list of stack numbers at the bottom.
+impl FromStr for CrateYard {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let lines: Vec<&str> = s.lines().collect();
+        if let Some(line_index) = lines.iter().position(|line| line.contains('\t')) {
+            return Err(eyre!(
+                "Found a tab at line {} of the drawing, crates must be aligned with spaces.",
+                line_index + 1
+            ));
+        }
+
+        let (number_list, crates) = lines
+            .split_last()
+            .ok_or_else(|| eyre!("Couldn't get list of number of stacks"))?;
+
+        let stack_columns = get_stack_columns(number_list)?;
+
+        fill_stacks(crates, &stack_columns)
+    }
+}
+
 #[aoc(day5, part1)]
 pub fn part1(input: &str) -> String {
-    let result = get_crates_number_of_stacks_and_moves(input)
-        .and_then(|(crates, number_of_stacks, moves)| fill_stacks(crates, number_of_stacks, moves))
-        .and_then(|(list_of_stacks, moves)| follow_moving_instructions_part1(list_of_stacks, moves))
-        .and_then(get_last_crate_of_each_stack);
+    let result = get_top_crates_after_moves(input, &CrateMover9000);
 
     match result {
         Ok(r) => r,
@@ -21,10 +285,7 @@ pub fn part1(input: &str) -> String {
 
 #[aoc(day5, part2)]
 pub fn part2(input: &str) -> String {
-    let result = get_crates_number_of_stacks_and_moves(input)
-        .and_then(|(crates, number_of_stacks, moves)| fill_stacks(crates, number_of_stacks, moves))
-        .and_then(|(list_of_stacks, moves)| follow_moving_instructions_part2(list_of_stacks, moves))
-        .and_then(get_last_crate_of_each_stack);
+    let result = get_top_crates_after_moves(input, &CrateMover9001);
 
     match result {
         Ok(r) => r,
@@ -32,140 +293,817 @@ pub fn part2(input: &str) -> String {
     }
 }
 
-fn get_crates_number_of_stacks_and_moves(input: &str) -> Result<(&str, u32, &str)> {
-    input
-        .split_once("\n\n")
-        .ok_or_else(|| eyre!("Couldn't get crates, number of stacks and moves."))
-        .and_then(|(crates_and_numbers, moves)| {
-            let number_list = crates_and_numbers
-                .lines()
-                .last()
-                .ok_or_else(|| eyre!("Couldn't get list of number of stacks"))?;
+#[aoc(day5, part1, Drawing)]
+pub fn part1_drawing(input: &str) -> String {
+    let result = get_yard_after_moves(input, &CrateMover9000);
 
-            let (crates, _) = crates_and_numbers
-                .split_once(number_list)
-                .ok_or_else(|| eyre!("Couldn't get crates without list of number of stacks."))?;
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
 
-            let number: u32 = number_list
-                .chars()
-                .nth_back(1)
-                .ok_or_else(|| eyre!("Couldn't get the max number of stacks."))?
-                .to_digit(10)
-                .ok_or_else(|| eyre!("Couldn't convert the max number of stacks."))?;
+#[aoc(day5, part2, Drawing)]
+pub fn part2_drawing(input: &str) -> String {
+    let result = get_yard_after_moves(input, &CrateMover9001);
 
-            Ok((crates, number, moves))
-        })
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day5, part1, Steps)]
+pub fn part1_steps(input: &str) -> String {
+    let result = step_through(input, &CrateMover9000);
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day5, part2, Steps)]
+pub fn part2_steps(input: &str) -> String {
+    let result = step_through(input, &CrateMover9001);
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
 }
 
-fn fill_stacks<'a>(
-    crates: &str,
-    number_of_stacks: u32,
-    moves: &'a str,
-) -> Result<(Vec<VecDeque<char>>, &'a str)> {
-    let mut list_of_stacks: Vec<VecDeque<char>> = Vec::new();
-    for stack_number in 0..number_of_stacks {
-        let mut stack: VecDeque<char> = VecDeque::new();
-        for line in crates.lines() {
-            let position: usize = (stack_number * 4) as usize + 1;
-            match line.chars().into_iter().nth(position) {
-                Some(c) if c.ne(&' ') => stack.push_front(c),
-                _ => (),
+#[aoc(day5, part1, Plan)]
+pub fn part1_plan(input: &str) -> String {
+    let result = get_plan(input, &CrateMover9000);
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day5, part2, Plan)]
+pub fn part2_plan(input: &str) -> String {
+    let result = get_plan(input, &CrateMover9001);
+
+    match result {
+        Ok(r) => format!("\n{}", r),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Plans new moves that lead to the same stacks as the puzzle's moves, in the puzzle's format.
+fn get_plan(input: &str, crane: &impl Crane) -> Result<String> {
+    let (yard, moves) = parse_input(input)?;
+    let mut target = yard.clone();
+    target.apply_all(&moves, crane)?;
+
+    plan_input(&yard, &target, crane)
+}
+
+#[aoc(day5, part1, History)]
+pub fn part1_history(input: &str) -> String {
+    let result = get_history(input, &CrateMover9000);
+
+    match result {
+        Ok(r) => r,
+        Err(e) => e.to_string(),
+    }
+}
+
+#[aoc(day5, part2, History)]
+pub fn part2_history(input: &str) -> String {
+    let result = get_history(input, &CrateMover9001);
+
+    match result {
+        Ok(r) => r,
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Follows every crate through the moves, and describes the tallest stack and the crate moved
+/// the most often.
+fn get_history(input: &str, crane: &impl Crane) -> Result<String> {
+    let (yard, moves) = parse_input(input)?;
+    let simulation = TrackedSimulation::run(&yard, &moves, crane)?;
+
+    let (stack, height) = simulation
+        .tallest_stack()
+        .ok_or_else(|| eyre!("The yard has no stacks."))?;
+    let mut summary = format!("Stack {} reached {} crates", stack + 1, height);
+    if let Some(most_moved) = simulation.most_moved() {
+        summary.push_str(&format!(
+            ", crate '{}' from stack {} moved {} times",
+            most_moved.label,
+            most_moved.origin.0 + 1,
+            most_moved.moves
+        ));
+    }
+
+    Ok(summary)
+}
+
+/// Parses the puzzle input, follows the moves with the given crane and reads the top crates.
+pub fn get_top_crates_after_moves(input: &str, crane: &impl Crane) -> Result<String> {
+    get_yard_after_moves(input, crane).map(|yard| yard.top_crates())
+}
+
+/// Parses the puzzle input and follows the moves with the given crane.
+pub fn get_yard_after_moves(input: &str, crane: &impl Crane) -> Result<CrateYard> {
+    let (mut yard, moves) = parse_input(input)?;
+    yard.apply_all(&moves, crane)?;
+
+    Ok(yard)
+}
+
+/// Draws the yard before the first move and after every move, each drawing preceded by the
+/// move and followed by an empty line.
+pub fn step_through(input: &str, crane: &impl Crane) -> Result<String> {
+    let (mut yard, moves) = parse_input(input)?;
+
+    let mut steps = format!("{}\n\n", yard);
+    for instruction in &moves {
+        yard.apply(&instruction.movement, crane)
+            .map_err(|e| instruction_error(instruction, e))?;
+        steps.push_str(&format!("{}\n{}\n\n", instruction.movement, yard));
+    }
+
+    Ok(steps)
+}
+
+/// Parses an input where the drawing shows the stacks after the moves, and finds how the stacks
+/// were before the moves were followed with the given crane.
+pub fn reverse_solve(input: &str, crane: &impl Crane) -> Result<CrateYard> {
+    let (mut yard, moves) = parse_input(input)?;
+    yard.undo_all(&moves, crane)?;
+
+    Ok(yard)
+}
+
+/// Finds moves that turn the start yard into the target yard with the given crane.
+///
+/// The target stacks are built from the bottom, one crate at a time: the crates above the next
+/// crate to place, and the ones above the level it goes to, are moved out of the way to another
+/// stack. Consecutive moves between the same stacks are then merged whenever the crane leaves
+/// the crates in the same order either way.
+///
+/// That needs a third stack to put crates out of the way, so smaller yards are solved by trying
+/// every sequence of moves instead.
+pub fn plan_moves(start: &CrateYard, target: &CrateYard, crane: &impl Crane) -> Result<Vec<Move>> {
+    check_same_crates(start, target)?;
+    if start == target {
+        return Ok(Vec::new());
+    }
+    if start.stacks.len() < 3 {
+        return search_moves(start, target, crane)?.ok_or_else(|| {
+            eyre!(
+                "Couldn't find moves to rearrange the crates with only {} stacks.",
+                start.stacks.len()
+            )
+        });
+    }
+
+    let mut planner = Planner {
+        yard: start.clone(),
+        moves: Vec::new(),
+        crane,
+    };
+    for (stack, target_stack) in target.stacks.iter().enumerate() {
+        for (level, c) in target_stack.iter().enumerate() {
+            planner.place(target, stack, level, *c)?;
+        }
+    }
+
+    merge_moves(start, &planner.moves, crane)
+}
+
+/// Most yards to go through when trying every sequence of moves.
+const MAX_SEARCHED_YARDS: usize = 10_000;
+
+/// Finds the shortest moves from the start yard to the target yard by trying every possible
+/// move from every yard reached so far, giving up after [`MAX_SEARCHED_YARDS`] yards.
+fn search_moves(
+    start: &CrateYard,
+    target: &CrateYard,
+    crane: &impl Crane,
+) -> Result<Option<Vec<Move>>> {
+    let mut seen = HashSet::from([start.clone()]);
+    // Every yard reached, with the yard it was reached from and the move that led to it
+    let mut reached: Vec<(CrateYard, Option<(usize, Move)>)> = vec![(start.clone(), None)];
+    let mut next = 0;
+    while next < reached.len() && reached.len() < MAX_SEARCHED_YARDS {
+        let yard = reached[next].0.clone();
+        for from in 0..yard.stacks.len() {
+            for to in (0..yard.stacks.len()).filter(|to| *to != from) {
+                for count in 1..=yard.stacks[from].len() {
+                    let movement = Move { count, from, to };
+                    let mut moved = yard.clone();
+                    moved.apply(&movement, crane)?;
+                    if !seen.insert(moved.clone()) {
+                        continue;
+                    }
+
+                    let found = moved == *target;
+                    reached.push((moved, Some((next, movement))));
+                    if found {
+                        let mut moves = Vec::new();
+                        let mut index = reached.len() - 1;
+                        while let Some((previous, movement)) = reached[index].1 {
+                            moves.push(movement);
+                            index = previous;
+                        }
+                        moves.reverse();
+                        return Ok(Some(moves));
+                    }
+                }
             }
         }
-        list_of_stacks.push(stack);
+        next += 1;
     }
 
-    Ok((list_of_stacks, moves))
+    Ok(None)
 }
 
-fn follow_moving_instructions_part1(
-    mut list_of_stacks: Vec<VecDeque<char>>,
-    moves: &str,
-) -> Result<Vec<VecDeque<char>>> {
-    let regex = Regex::new(REGEX)?;
-    for line in moves.lines() {
-        let (number_of_movements, origin, destination) = parse_instructions(line, &regex)?;
-        for _ in 0..number_of_movements {
-            let origin_stack: &mut VecDeque<char> =
-                list_of_stacks
-                    .as_mut_slice()
-                    .get_mut(origin)
-                    .ok_or_else(|| eyre!("Couldn't get origin stack."))?;
-
-            let moved_crate = origin_stack
-                .pop_back()
-                .ok_or_else(|| eyre!("Couldn't pop crate from origin stack."))?;
-
-            let destination_stack: &mut VecDeque<char> = list_of_stacks
-                .as_mut_slice()
-                .get_mut(destination)
-                .ok_or_else(|| eyre!("Couldn't get destination stack."))?;
-
-            destination_stack.push_back(moved_crate);
+/// Writes the start yard and the moves that turn it into the target yard in the puzzle's
+/// format, so the result can be solved like the puzzle input.
+pub fn plan_input(start: &CrateYard, target: &CrateYard, crane: &impl Crane) -> Result<String> {
+    let moves: String = plan_moves(start, target, crane)?
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect();
+
+    Ok(format!("{}\n\n{}", start, moves))
+}
+
+/// Checks both yards have as many stacks, and the same crates.
+fn check_same_crates(start: &CrateYard, target: &CrateYard) -> Result<()> {
+    if start.stacks.len() != target.stacks.len() {
+        return Err(eyre!(
+            "The start yard has {} stacks but the target yard has {}.",
+            start.stacks.len(),
+            target.stacks.len()
+        ));
+    }
+
+    let sorted_crates = |yard: &CrateYard| {
+        let mut crates: Vec<char> = yard.stacks.iter().flatten().copied().collect();
+        crates.sort_unstable();
+        crates
+    };
+    if sorted_crates(start) != sorted_crates(target) {
+        return Err(eyre!(
+            "The start and target yards don't have the same crates."
+        ));
+    }
+
+    Ok(())
+}
+
+/// Follows the moves as they're planned, so each one is planned from the actual stacks.
+struct Planner<'a, C: Crane> {
+    yard: CrateYard,
+    moves: Vec<Move>,
+    crane: &'a C,
+}
+
+impl<C: Crane> Planner<'_, C> {
+    /// Puts the crate at the given level of the stack, without touching the crates already
+    /// placed: the levels below in this stack, and the target crates of the previous stacks.
+    fn place(&mut self, target: &CrateYard, stack: usize, level: usize, c: char) -> Result<()> {
+        let is_placed = |index: usize, position: usize| match index.cmp(&stack) {
+            std::cmp::Ordering::Less => position < target.stacks[index].len(),
+            std::cmp::Ordering::Equal => position < level,
+            std::cmp::Ordering::Greater => false,
+        };
+
+        let (source, position) = self
+            .yard
+            .stacks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, crates)| {
+                crates
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, other)| **other == c)
+                    .map(move |(position, _)| (index, position))
+            })
+            .filter(|(index, position)| !is_placed(*index, *position))
+            .min_by_key(|(index, position)| {
+                if (*index, *position) == (stack, level) {
+                    0
+                } else {
+                    self.yard.stacks[*index].len() - position
+                }
+            })
+            .ok_or_else(|| eyre!("Couldn't find crate '{}' to put in stack {}.", c, stack + 1))?;
+
+        if source == stack {
+            if position == level {
+                return Ok(());
+            }
+            let helper = self.helper(&[stack])?;
+            self.push(self.yard.stacks[stack].len() - position - 1, stack, helper)?;
+            self.push(1, stack, helper)?;
+            let other_helper = self.helper(&[stack, helper])?;
+            self.push(position - level, stack, other_helper)?;
+            self.push(1, helper, stack)
+        } else {
+            let helper = self.helper(&[stack, source])?;
+            self.push(
+                self.yard.stacks[source].len() - position - 1,
+                source,
+                helper,
+            )?;
+            self.push(self.yard.stacks[stack].len() - level, stack, helper)?;
+            self.push(1, source, stack)
         }
     }
 
-    Ok(list_of_stacks)
+    /// A stack to put crates out of the way, preferably one that isn't built yet.
+    fn helper(&self, excluded: &[usize]) -> Result<usize> {
+        (0..self.yard.stacks.len())
+            .rev()
+            .find(|index| !excluded.contains(index))
+            .ok_or_else(|| eyre!("Couldn't find a stack to put crates out of the way."))
+    }
+
+    fn push(&mut self, count: usize, from: usize, to: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let instruction = Move { count, from, to };
+        self.yard.apply(&instruction, self.crane)?;
+        self.moves.push(instruction);
+
+        Ok(())
+    }
 }
 
-fn follow_moving_instructions_part2(
-    mut list_of_stacks: Vec<VecDeque<char>>,
-    moves: &str,
-) -> Result<Vec<VecDeque<char>>> {
-    let regex = Regex::new(REGEX)?;
-    for line in moves.lines() {
-        let (number_of_movements, origin, destination) = parse_instructions(line, &regex)?;
+/// Merges each move with the previous one when they are between the same stacks and the crane
+/// leaves the crates in the same order with the merged move.
+fn merge_moves(start: &CrateYard, moves: &[Move], crane: &impl Crane) -> Result<Vec<Move>> {
+    let mut merged: Vec<Move> = Vec::new();
+    let mut before_last = start.clone();
+    let mut yard = start.clone();
+    for instruction in moves {
+        let mut after = yard.clone();
+        after.apply(instruction, crane)?;
+
+        if let Some(last) = merged.last_mut() {
+            if (last.from, last.to) == (instruction.from, instruction.to) {
+                let candidate = Move {
+                    count: last.count + instruction.count,
+                    ..*last
+                };
+                let mut merged_yard = before_last.clone();
+                merged_yard.apply(&candidate, crane)?;
+                if merged_yard == after {
+                    *last = candidate;
+                    yard = after;
+                    continue;
+                }
+            }
+        }
+
+        merged.push(*instruction);
+        before_last = std::mem::replace(&mut yard, after);
+    }
 
-        let origin_stack: &mut VecDeque<char> = list_of_stacks
-            .as_mut_slice()
-            .get_mut(origin)
-            .ok_or_else(|| eyre!("Couldn't get origin stack."))?;
+    Ok(merged)
+}
 
-        let mut crate_movement_queue: VecDeque<char> = VecDeque::new();
+/// What happened to a crate during the moves. Positions are a stack and a level, both
+/// numbered from 0, the bottom level being 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateHistory {
+    pub label: char,
+    pub origin: (usize, usize),
+    pub position: (usize, usize),
+    /// Number of instructions that moved the crate.
+    pub moves: usize,
+}
 
-        for _ in 0..number_of_movements {
-            let moved_crate = origin_stack
-                .pop_back()
-                .ok_or_else(|| eyre!("Couldn't pop crate from origin stack."))?;
-            crate_movement_queue.push_back(moved_crate);
+/// Result of following the moves while keeping track of each crate, even those with the same
+/// label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedSimulation {
+    /// Sorted by origin.
+    crates: Vec<CrateHistory>,
+    max_heights: Vec<usize>,
+}
+
+impl TrackedSimulation {
+    /// Follows every instruction in order with the given crane, moving crate ids instead of
+    /// labels.
+    pub fn run(yard: &CrateYard, instructions: &[Instruction], crane: &impl Crane) -> Result<Self> {
+        let mut crates = Vec::new();
+        let mut stacks: Vec<Vec<usize>> = Vec::with_capacity(yard.stacks.len());
+        for (stack, labels) in yard.stacks.iter().enumerate() {
+            let mut ids = Vec::with_capacity(labels.len());
+            for (level, label) in labels.iter().enumerate() {
+                ids.push(crates.len());
+                crates.push(CrateHistory {
+                    label: *label,
+                    origin: (stack, level),
+                    position: (stack, level),
+                    moves: 0,
+                });
+            }
+            stacks.push(ids);
         }
+        let mut max_heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
 
-        let destination_stack: &mut VecDeque<char> = list_of_stacks
-            .as_mut_slice()
-            .get_mut(destination)
-            .ok_or_else(|| eyre!("Couldn't get destination stack."))?;
+        for instruction in instructions {
+            let Move { count, to, .. } = instruction.movement;
+            apply_move(&mut stacks, &instruction.movement, crane)
+                .map_err(|e| instruction_error(instruction, e))?;
 
-        for _ in 0..number_of_movements {
-            let moved_crate = crate_movement_queue
-                .pop_back()
-                .ok_or_else(|| eyre!("Couldn't pop crate from intermediate stack."))?;
+            let destination = &stacks[to];
+            for id in &destination[destination.len() - count..] {
+                crates[*id].moves += 1;
+            }
+            max_heights[to] = max_heights[to].max(destination.len());
+        }
 
-            destination_stack.push_back(moved_crate);
+        for (stack, ids) in stacks.iter().enumerate() {
+            for (level, id) in ids.iter().enumerate() {
+                crates[*id].position = (stack, level);
+            }
         }
+
+        Ok(TrackedSimulation {
+            crates,
+            max_heights,
+        })
+    }
+
+    pub fn crates(&self) -> &[CrateHistory] {
+        &self.crates
+    }
+
+    /// The crate that started at the given stack and level.
+    pub fn from_origin(&self, stack: usize, level: usize) -> Option<&CrateHistory> {
+        self.crates
+            .binary_search_by_key(&(stack, level), |history| history.origin)
+            .ok()
+            .map(|index| &self.crates[index])
+    }
+
+    /// The crate that ended up at the given stack and level.
+    pub fn at_position(&self, stack: usize, level: usize) -> Option<&CrateHistory> {
+        self.crates
+            .iter()
+            .find(|history| history.position == (stack, level))
+    }
+
+    /// Highest number of crates each stack had at any point.
+    pub fn max_heights(&self) -> &[usize] {
+        &self.max_heights
+    }
+
+    /// The stack that was the highest at any point, and its height. Ties go to the first stack.
+    pub fn tallest_stack(&self) -> Option<(usize, usize)> {
+        self.max_heights
+            .iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, height)| *height)
     }
 
-    Ok(list_of_stacks)
+    /// The crate moved the most often. Ties go to the crate with the lowest origin.
+    pub fn most_moved(&self) -> Option<&CrateHistory> {
+        self.crates.iter().rev().max_by_key(|history| history.moves)
+    }
+
+    /// The stacks after the moves.
+    pub fn yard(&self) -> CrateYard {
+        let mut stacks = vec![Vec::new(); self.max_heights.len()];
+        let mut by_position: Vec<&CrateHistory> = self.crates.iter().collect();
+        by_position.sort_by_key(|history| history.position);
+        for history in by_position {
+            stacks[history.position.0].push(history.label);
+        }
+
+        CrateYard::new(stacks)
+    }
 }
 
-fn parse_instructions(line: &str, regex: &Regex) -> Result<(u32, usize, usize)> {
-    let groups = regex
-        .captures(line)
-        .ok_or_else(|| eyre!("Couldn't parse instruction groups."))?;
+/// Settings of a random puzzle input. The same settings always give the same input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputGenerator {
+    pub stacks: usize,
+    /// Number of crates in each stack before the moves.
+    pub height: usize,
+    /// Crates are picked from these, with repetitions.
+    pub alphabet: Vec<char>,
+    pub moves: usize,
+    /// Most crates taken in one move, or as many as the origin stack has when `None`.
+    pub max_count: Option<usize>,
+    pub seed: u64,
+}
 
-    let number_of_movements = groups[1].parse::<u32>()?;
-    let origin = groups[2].parse::<usize>()?;
-    let destination = groups[3].parse::<usize>()?;
+impl Default for InputGenerator {
+    fn default() -> Self {
+        InputGenerator {
+            stacks: 9,
+            height: 8,
+            alphabet: ('A'..='Z').collect(),
+            moves: 500,
+            max_count: None,
+            seed: 2022,
+        }
+    }
+}
+
+impl InputGenerator {
+    /// Generates the stacks and moves that never take more crates than the origin stack has,
+    /// nor move crates to the stack they come from. Moves are numbered with their line in
+    /// [`InputGenerator::generate_input`].
+    pub fn generate(&self) -> Result<(CrateYard, Vec<Instruction>)> {
+        if self.stacks == 0 {
+            return Err(eyre!("The yard needs at least one stack."));
+        }
+        if self.alphabet.is_empty() && self.height > 0 {
+            return Err(eyre!("The alphabet needs at least one crate."));
+        }
+        if let Some(c) = self
+            .alphabet
+            .iter()
+            .find(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(eyre!("Crate {:?} wouldn't show in the drawing.", c));
+        }
+        if self.moves > 0 && (self.stacks < 2 || self.height == 0) {
+            return Err(eyre!(
+                "Moves need at least 2 stacks and one crate, found {} stacks of {} crates.",
+                self.stacks,
+                self.height
+            ));
+        }
+        if self.moves > 0 && self.max_count == Some(0) {
+            return Err(eyre!("Moves need to take at least one crate."));
+        }
+
+        let mut rng = SplitMix64(self.seed);
+        let stacks: Vec<Vec<char>> = (0..self.stacks)
+            .map(|_| {
+                (0..self.height)
+                    .map(|_| self.alphabet[rng.below(self.alphabet.len())])
+                    .collect()
+            })
+            .collect();
+
+        let yard = CrateYard::new(stacks);
+        let first_line = yard.to_string().lines().count() + 2;
+
+        let mut sizes = vec![self.height; self.stacks];
+        let mut moves = Vec::with_capacity(self.moves);
+        for index in 0..self.moves {
+            let origins: Vec<usize> = (0..self.stacks).filter(|s| sizes[*s] > 0).collect();
+            let from = origins[rng.below(origins.len())];
+            let to = match rng.below(self.stacks - 1) {
+                to if to >= from => to + 1,
+                to => to,
+            };
+            let count = 1 + rng.below(sizes[from].min(self.max_count.unwrap_or(usize::MAX)));
+
+            sizes[from] -= count;
+            sizes[to] += count;
+            moves.push(Instruction {
+                line: first_line + index,
+                movement: Move { count, from, to },
+            });
+        }
+
+        Ok((yard, moves))
+    }
+
+    /// Generates an input in the puzzle's format.
+    pub fn generate_input(&self) -> Result<String> {
+        let (yard, moves) = self.generate()?;
+        let moves: String = moves
+            .iter()
+            .map(|instruction| format!("{}\n", instruction.movement))
+            .collect();
 
-    Ok((number_of_movements, origin - 1, destination - 1))
+        Ok(format!("{}\n\n{}", yard, moves))
+    }
 }
 
-fn get_last_crate_of_each_stack(list_of_stacks: Vec<VecDeque<char>>) -> Result<String> {
-    let last_crates: String = list_of_stacks
-        .into_iter()
-        .map(|stack| stack.back().map_or_else(String::new, ToString::to_string))
-        .collect();
+/// SplitMix64 generator, which works with any seed, even 0.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Parses the drawing of the stacks and the list of moves.
+pub fn parse_input(input: &str) -> Result<(CrateYard, Vec<Instruction>)> {
+    let (drawing, moves) = get_drawing_and_moves(input)?;
+    let first_line = drawing.lines().count() + 2;
+
+    Ok((drawing.parse()?, parse_moves(moves, first_line)?))
+}
+
+/// Parses every non-blank line as a move, numbering the lines from `first_line`.
+fn parse_moves(moves: &str, first_line: usize) -> Result<Vec<Instruction>> {
+    moves
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line_number = first_line + index;
+            line.parse()
+                .map(|movement| Instruction {
+                    line: line_number,
+                    movement,
+                })
+                .map_err(|e| eyre!("Line {} '{}': {}", line_number, line, e))
+        })
+        .collect()
+}
+
+fn instruction_error(instruction: &Instruction, error: Report) -> Report {
+    eyre!(
+        "Line {} '{}': {}",
+        instruction.line,
+        instruction.movement,
+        error
+    )
+}
+
+/// Splits the input in the drawing of the stacks, with their numbers, and the moves, which are
+/// separated by the first blank line. Lines may end with `\n` or `\r\n`.
+fn get_drawing_and_moves(input: &str) -> Result<(&str, &str)> {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            let drawing = input[..offset].trim_end_matches(['\r', '\n']);
+            return Ok((drawing, &input[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    Err(eyre!("Couldn't get crates, number of stacks and moves."))
+}
+
+/// Moves crates between two stacks of the list with the given crane.
+///
+/// This is the only place where crates are moved, whatever the crane and the kind of crates.
+fn apply_move<T>(stacks: &mut [Vec<T>], instruction: &Move, crane: &impl Crane) -> Result<()> {
+    let (origin, destination) = get_stacks_mut(stacks, instruction, instruction.from)?;
+    crane.lift(origin, destination, instruction.count);
+
+    Ok(())
+}
+
+/// Moves crates back between two stacks of the list, as the crane would have to undo the move.
+fn undo_move<T>(stacks: &mut [Vec<T>], instruction: &Move, crane: &impl Crane) -> Result<()> {
+    let (origin, destination) = get_stacks_mut(stacks, instruction, instruction.to)?;
+    crane.unlift(origin, destination, instruction.count);
+
+    Ok(())
+}
+
+/// Borrows the origin and destination stacks of the instruction, after checking they're
+/// different stacks of the list and that `source`, the stack crates are taken from, has enough
+/// of them.
+fn get_stacks_mut<'a, T>(
+    stacks: &'a mut [Vec<T>],
+    instruction: &Move,
+    source: usize,
+) -> Result<(&'a mut Vec<T>, &'a mut Vec<T>)> {
+    let Move { count, from, to } = *instruction;
+    let error = |message: String| {
+        let sizes: Vec<String> = stacks.iter().map(|s| s.len().to_string()).collect();
+        Err(eyre!("{} Stack sizes: {}.", message, sizes.join(", ")))
+    };
+
+    if let Some(stack) = [from, to].into_iter().find(|stack| *stack >= stacks.len()) {
+        return error(format!(
+            "There is no stack {}, the yard has {} stacks.",
+            stack + 1,
+            stacks.len()
+        ));
+    }
+    if from == to {
+        return error(format!(
+            "Can't move crates from stack {} to itself.",
+            from + 1
+        ));
+    }
+    if stacks[source].len() < count {
+        return error(format!(
+            "Can't move {} crates from stack {}, it only has {}.",
+            count,
+            source + 1,
+            stacks[source].len()
+        ));
+    }
+
+    if from < to {
+        let (left, right) = stacks.split_at_mut(to);
+        Ok((&mut left[from], &mut right[0]))
+    } else {
+        let (left, right) = stacks.split_at_mut(from);
+        Ok((&mut right[0], &mut left[to]))
+    }
+}
+
+/// Gets the first and last column of each number in the list of stacks, which must be
+/// `1 2 3 ...` separated by any amount of whitespace.
+fn get_stack_columns(number_list: &str) -> Result<StackColumns> {
+    let mut stack_columns: Vec<(usize, usize)> = Vec::new();
+    let mut number = String::new();
+    for (column, c) in number_list.chars().chain([' ']).enumerate() {
+        if c.is_ascii_digit() {
+            number.push(c);
+        } else if !c.is_whitespace() {
+            return Err(eyre!(
+                "Unexpected '{}' at column {} of the list of stacks.",
+                c,
+                column + 1
+            ));
+        } else if !number.is_empty() {
+            let expected = stack_columns.len() + 1;
+            if number.parse::<usize>().ok() != Some(expected) {
+                return Err(eyre!(
+                    "Expected stack number {}, found {} in the list of stacks.",
+                    expected,
+                    number
+                ));
+            }
+            stack_columns.push((column - number.len(), column - 1));
+            number.clear();
+        }
+    }
+
+    if stack_columns.is_empty() {
+        return Err(eyre!("Couldn't find any stack in the list of stacks."));
+    }
 
-    Ok(last_crates)
+    Ok(stack_columns)
+}
+
+/// Reads every `[X]` crate of the drawing and puts it in the stack whose number is right below
+/// the crate's letter. Lines may be shorter than the list of stacks.
+fn fill_stacks(crates: &[&str], stack_columns: &[(usize, usize)]) -> Result<CrateYard> {
+    let mut list_of_stacks: Vec<Vec<char>> = vec![Vec::new(); stack_columns.len()];
+    for (line_index, line) in crates.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut column = 0;
+        while column < chars.len() {
+            match chars[column..] {
+                [' ', ..] => column += 1,
+                ['[', c, ']', ..] => {
+                    let letter = column + 1;
+                    let stack = stack_columns.partition_point(|(_, last)| *last < letter);
+                    if stack_columns
+                        .get(stack)
+                        .is_none_or(|(first, _)| *first > letter)
+                    {
+                        return Err(eyre!(
+                            "Crate '{}' at line {}, column {} doesn't line up with any stack \
+                             number.",
+                            c,
+                            line_index + 1,
+                            letter + 1
+                        ));
+                    }
+                    list_of_stacks[stack].push(c);
+                    column += 3;
+                }
+                _ => {
+                    return Err(eyre!(
+                        "Expected a crate like '[X]' at line {}, column {}.",
+                        line_index + 1,
+                        column + 1
+                    ))
+                }
+            }
+        }
+    }
+
+    for stack in &mut list_of_stacks {
+        stack.reverse();
+    }
+
+    Ok(CrateYard::new(list_of_stacks))
 }
 
 #[cfg(test)]
@@ -173,37 +1111,173 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
+    const DRAWING: &str = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n";
+    const MOVES: &str =
+        "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+
+    fn example_yard() -> CrateYard {
+        CrateYard::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']])
+    }
+
+    /// The moves of the example input, which start at line 6.
+    fn example_moves() -> Vec<Instruction> {
+        parse_moves(MOVES, 6).unwrap()
+    }
+
     #[test]
-    fn test_get_crates_number_of_stacks_and_moves() {
-        let crates = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n";
-        let numbers: &str = "     1   2   3 \n";
-        let number: u32 = 3;
-        let moves: &str =
-            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+    fn test_get_drawing_and_moves() {
+        let input = format!("{}\n{}", DRAWING, MOVES);
 
-        let input = format!("{}{}\n{}", crates, numbers, moves);
+        let output = get_drawing_and_moves(&input).unwrap();
+
+        assert_eq!(output, (DRAWING.strip_suffix('\n').unwrap(), MOVES));
+    }
+
+    #[test]
+    fn test_crate_yard_from_str() {
+        let yard: CrateYard = DRAWING.parse().unwrap();
+
+        assert_eq!(yard, example_yard());
+    }
+
+    #[test]
+    fn test_crate_yard_display() {
+        let mut yard = example_yard();
+        yard.apply_all(&example_moves(), &CrateMover9000).unwrap();
+
+        let expected = "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 ";
+
+        assert_eq!(yard.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(DRAWING.strip_suffix('\n').unwrap())]
+    #[case(get_drawing_and_moves(include_str!("../input/2022/day5.txt")).unwrap().0)]
+    fn test_crate_yard_round_trip(#[case] drawing: &str) {
+        let yard: CrateYard = drawing.parse().unwrap();
+
+        assert_eq!(yard.to_string(), drawing);
+    }
+
+    #[test]
+    fn test_crate_yard_round_trip_with_more_than_nine_stacks() {
+        let mut stacks = vec![vec!['A']; 12];
+        stacks[10].push('B');
+        let yard = CrateYard::new(stacks);
+
+        assert_eq!(yard.to_string().parse::<CrateYard>().unwrap(), yard);
+    }
+
+    #[test]
+    fn test_step_through() {
+        let input = "[A]    \n[B] [C]\n 1   2 \n\nmove 2 from 1 to 2\n";
+
+        let expected = "[A]    \n[B] [C]\n 1   2 \n\n\
+                        move 2 from 1 to 2\n    [B]\n    [A]\n    [C]\n 1   2 \n\n";
+
+        assert_eq!(step_through(input, &CrateMover9000).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_steps_runners() {
+        let input = "[A]    \n[B] [C]\n 1   2 \n\nmove 2 from 1 to 2\n";
+
+        assert!(part1_steps(input).ends_with("    [B]\n    [A]\n    [C]\n 1   2 \n\n"));
+        assert!(part2_steps(input).ends_with("    [A]\n    [B]\n    [C]\n 1   2 \n\n"));
+    }
 
-        let output = get_crates_number_of_stacks_and_moves(&input).unwrap();
+    #[rstest]
+    #[case("    [D]    \r\n[N] [C]    \r\n[Z] [M] [P]\r\n 1   2   3 \r\n\r\n")]
+    #[case("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n\n")]
+    #[case("    [D]\r\n[N] [C]\r\n[Z] [M] [P]\r\n 1   2   3\r\n  \r\n")]
+    fn test_parse_input_with_crlf_and_trimmed_lines(#[case] drawing: &str) {
+        let moves = MOVES.replace('\n', "\r\n") + "\r\n";
+
+        let (yard, output) = parse_input(&format!("{}{}", drawing, moves)).unwrap();
 
-        assert_eq!(output.0, crates);
-        assert_eq!(output.1, number);
-        assert_eq!(output.2, moves);
+        assert_eq!(yard, example_yard());
+        assert_eq!(output, example_moves());
+    }
+
+    #[rstest]
+    #[case(
+        "    [D]    \n[N]\t[C]    \n[Z] [M] [P]\n 1   2   3 ",
+        "Found a tab at line 2 of the drawing, crates must be aligned with spaces."
+    )]
+    #[case(
+        "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1\t2\t3",
+        "Found a tab at line 4 of the drawing, crates must be aligned with spaces."
+    )]
+    #[case(
+        "     [D]   \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ",
+        "Crate 'D' at line 1, column 7 doesn't line up with any stack number."
+    )]
+    #[case(
+        "    [D]    \n[N] [C]    \n[Z] [M] [P] [Q]\n 1   2   3 ",
+        "Crate 'Q' at line 3, column 14 doesn't line up with any stack number."
+    )]
+    #[case(
+        "    [D]    \n[N] [C]    \n[Z] [M]  P]\n 1   2   3 ",
+        "Expected a crate like '[X]' at line 3, column 10."
+    )]
+    fn test_crate_yard_from_str_fail(#[case] drawing: &str, #[case] expected: &str) {
+        let output = drawing.parse::<CrateYard>();
+
+        assert_eq!(output.unwrap_err().to_string(), expected);
     }
 
     #[test]
     fn test_fill_stacks() {
-        let crates = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n";
-        let number_of_stacks: u32 = 3;
-        let moves: &str =
-            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+        let crates = ["    [D]    ", "[N] [C]    ", "[Z] [M] [P]"];
+        let stack_columns = vec![(1, 1), (5, 5), (9, 9)];
+
+        let output = fill_stacks(&crates, &stack_columns).unwrap();
+
+        assert_eq!(output.stacks()[0], ['Z', 'N']);
+        assert_eq!(output.stacks()[1], ['M', 'C', 'D']);
+        assert_eq!(output.stacks()[2], ['P']);
+    }
+
+    #[rstest]
+    #[case(" 1   2   3 ", vec![(1, 1), (5, 5), (9, 9)])]
+    #[case(" 1   2   3", vec![(1, 1), (5, 5), (9, 9)])]
+    #[case("1 2  3", vec![(0, 0), (2, 2), (5, 5)])]
+    fn test_get_stack_columns(#[case] number_list: &str, #[case] expected: Vec<(usize, usize)>) {
+        assert_eq!(get_stack_columns(number_list).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_get_stack_columns_with_more_than_nine_stacks() {
+        let number_list = " 1   2   3   4   5   6   7   8   9   10  11 ";
 
-        let output = fill_stacks(crates, number_of_stacks, moves).unwrap();
-        let list_of_stacks = output.0;
+        let output = get_stack_columns(number_list).unwrap();
 
-        assert_eq!(list_of_stacks[0], VecDeque::from(['Z', 'N']));
-        assert_eq!(list_of_stacks[1], VecDeque::from(['M', 'C', 'D']));
-        assert_eq!(list_of_stacks[2], VecDeque::from(['P']));
-        assert_eq!(output.1, moves);
+        assert_eq!(output.len(), 11);
+        assert_eq!(output[8], (33, 33));
+        assert_eq!(output[9], (37, 38));
+        assert_eq!(output[10], (41, 42));
+    }
+
+    #[rstest]
+    #[case(" 1   3 ")]
+    #[case(" 1   2a ")]
+    #[case("   ")]
+    fn test_get_stack_columns_fail(#[case] number_list: &str) {
+        assert!(get_stack_columns(number_list).is_err());
+    }
+
+    #[test]
+    fn test_part1_with_more_than_nine_stacks() {
+        let crates = format!(
+            "[A]{}[K]\n[B] [C] [D] [E] [F] [G] [H] [I] [J] [L] [M] [N]\n",
+            " ".repeat(37)
+        );
+        let numbers = " 1   2   3   4   5   6   7   8   9   10  11  12\n";
+        let moves = "move 2 from 1 to 11\nmove 1 from 12 to 10\n";
+
+        let input = format!("{}{}\n{}", crates, numbers, moves);
+
+        assert_eq!(part1(&input), "CDEFGHIJNB");
     }
 
     #[rstest]
@@ -212,58 +1286,432 @@ mod tests {
     #[case("move 2 from 2 to 1", (2, 1, 0))]
     #[case("move 1 from 1 to 2", (1, 0, 1))]
     #[case("move 31 from 7 to 1", (31, 6, 0))]
-    fn test_parse_instructions(#[case] line: &str, #[case] expected: (u32, usize, usize)) {
-        let regex = Regex::new(REGEX).unwrap();
-        let output = parse_instructions(line, &regex).unwrap();
+    fn test_move_from_str(#[case] line: &str, #[case] expected: (usize, usize, usize)) {
+        let output: Move = line.parse().unwrap();
+
+        assert_eq!((output.count, output.from, output.to), expected);
+        assert_eq!(output.to_string(), line);
+    }
+
+    #[test]
+    fn test_crate_mover_9000() {
+        let mut yard = example_yard();
+
+        yard.apply_all(&example_moves(), &CrateMover9000).unwrap();
+
+        assert_eq!(
+            yard,
+            CrateYard::new(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']])
+        );
+        assert_eq!(yard.top_crates(), "CMZ");
+    }
+
+    #[test]
+    fn test_crate_mover_9001() {
+        let mut yard = example_yard();
+
+        yard.apply_all(&example_moves(), &CrateMover9001).unwrap();
+
+        assert_eq!(
+            yard,
+            CrateYard::new(vec![vec!['M'], vec!['C'], vec!['P', 'Z', 'N', 'D']])
+        );
+        assert_eq!(yard.top_crates(), "MCD");
+    }
+
+    #[rstest]
+    #[case(1, vec!['F', 'E', 'D', 'C', 'B'])]
+    #[case(2, vec!['E', 'F', 'C', 'D', 'B'])]
+    #[case(3, vec!['D', 'E', 'F', 'B', 'C'])]
+    #[case(5, vec!['B', 'C', 'D', 'E', 'F'])]
+    fn test_capped_crane(#[case] capacity: usize, #[case] expected: Vec<char>) {
+        let mut yard = CrateYard::new(vec![vec!['A', 'B', 'C', 'D', 'E', 'F'], vec![]]);
+        let crane = CappedCrane::new(capacity).unwrap();
+
+        yard.apply(
+            &Move {
+                count: 5,
+                from: 0,
+                to: 1,
+            },
+            &crane,
+        )
+        .unwrap();
+
+        assert_eq!(yard.stacks(), [vec!['A'], expected]);
+    }
+
+    #[test]
+    fn test_cranes_with_large_moves() {
+        let crates: Vec<u32> = (0..20_000).collect();
+        let mut stacks = vec![crates.clone(), Vec::new()];
+        let instruction = Move {
+            count: 10_000,
+            from: 0,
+            to: 1,
+        };
 
-        assert_eq!(output, expected);
+        apply_move(&mut stacks, &instruction, &CrateMover9000).unwrap();
+        assert!(stacks[1].iter().rev().eq(&crates[10_000..]));
+
+        apply_move(&mut stacks, &instruction, &CrateMover9001).unwrap();
+        assert!(stacks[0].is_empty());
+        assert_eq!(stacks[1][10_000..], crates[..10_000]);
+
+        let mut capped = vec![crates.clone(), Vec::new()];
+        apply_move(&mut capped, &instruction, &CappedCrane::new(1).unwrap()).unwrap();
+        assert!(capped[1].iter().rev().eq(&crates[10_000..]));
+    }
+
+    #[test]
+    fn test_reverse_solve() {
+        let input = format!(
+            "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 \n\n{}",
+            MOVES
+        );
+
+        assert_eq!(
+            reverse_solve(&input, &CrateMover9000).unwrap(),
+            example_yard()
+        );
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(3)]
+    #[case(7)]
+    #[case(50)]
+    fn test_undo_all(#[case] capacity: usize) {
+        fn round_trip(yard: &CrateYard, moves: &[Instruction], crane: &impl Crane) -> CrateYard {
+            let mut output = yard.clone();
+            output.apply_all(moves, crane).unwrap();
+            output.undo_all(moves, crane).unwrap();
+            output
+        }
+
+        let (yard, moves) = parse_input(include_str!("../input/2022/day5.txt")).unwrap();
+
+        assert_eq!(round_trip(&yard, &moves, &CrateMover9000), yard);
+        assert_eq!(round_trip(&yard, &moves, &CrateMover9001), yard);
+        let crane = CappedCrane::new(capacity).unwrap();
+        assert_eq!(round_trip(&yard, &moves, &crane), yard);
     }
 
     #[test]
-    fn test_follow_moving_instructions_part1() {
-        let list_of_stacks = vec![
-            VecDeque::from(['Z', 'N']),
-            VecDeque::from(['M', 'C', 'D']),
-            VecDeque::from(['P']),
-        ];
-        let moves: &str =
-            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+    fn test_undo_all_fail() {
+        let mut yard = example_yard();
+
+        let output = yard.undo_all(&example_moves(), &CrateMover9000);
+
+        assert_eq!(
+            output.unwrap_err().to_string(),
+            "Line 7 'move 3 from 1 to 3': Can't move 3 crates from stack 3, it only has 1. \
+             Stack sizes: 1, 4, 1."
+        );
+    }
+
+    #[rstest]
+    #[case(CrateMover9000)]
+    #[case(CrateMover9001)]
+    #[case(CappedCrane::new(2).unwrap())]
+    fn test_plan_moves(#[case] crane: impl Crane) {
+        let target = CrateYard::new(vec![vec!['D', 'Z'], vec![], vec!['N', 'P', 'C', 'M']]);
+
+        let plan = plan_moves(&example_yard(), &target, &crane).unwrap();
+        let mut output = example_yard();
+        for instruction in &plan {
+            output.apply(instruction, &crane).unwrap();
+        }
+
+        assert_eq!(output, target);
+    }
 
-        let output = follow_moving_instructions_part1(list_of_stacks, moves).unwrap();
+    #[rstest]
+    #[case(CrateMover9000)]
+    #[case(CrateMover9001)]
+    #[case(CappedCrane::new(3).unwrap())]
+    fn test_plan_input(#[case] crane: impl Crane + Copy) {
+        let (start, moves) = parse_input(include_str!("../input/2022/day5.txt")).unwrap();
+        let mut target = start.clone();
+        target.apply_all(&moves, &crane).unwrap();
+
+        let input = plan_input(&start, &target, &crane).unwrap();
 
-        assert_eq!(output[0], VecDeque::from(['C']));
-        assert_eq!(output[1], VecDeque::from(['M']));
-        assert_eq!(output[2], VecDeque::from(['P', 'D', 'N', 'Z']));
+        assert_eq!(get_yard_after_moves(&input, &crane).unwrap(), target);
+        assert!(parse_input(&input).unwrap().1.len() < moves.len());
     }
 
     #[test]
-    fn test_follow_moving_instructions_part2() {
-        let list_of_stacks = vec![
-            VecDeque::from(['Z', 'N']),
-            VecDeque::from(['M', 'C', 'D']),
-            VecDeque::from(['P']),
-        ];
-        let moves: &str =
-            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+    fn test_plan_moves_merges_moves() {
+        let target = CrateYard::new(vec![vec![], vec!['M', 'C', 'D', 'N', 'Z'], vec!['P']]);
 
-        let output = follow_moving_instructions_part2(list_of_stacks, moves).unwrap();
+        let plan = plan_moves(&example_yard(), &target, &CrateMover9000).unwrap();
 
-        assert_eq!(output[0], VecDeque::from(['M']));
-        assert_eq!(output[1], VecDeque::from(['C']));
-        assert_eq!(output[2], VecDeque::from(['P', 'Z', 'N', 'D']));
+        assert_eq!(
+            plan,
+            [Move {
+                count: 2,
+                from: 0,
+                to: 1
+            }]
+        );
     }
+
+    #[rstest]
+    #[case(CrateYard::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D']]))]
+    #[case(CrateYard::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['Q']]))]
+    fn test_plan_moves_fail(#[case] target: CrateYard) {
+        assert!(plan_moves(&example_yard(), &target, &CrateMover9000).is_err());
+    }
+
     #[test]
-    fn test_get_last_crate_of_each_stack() {
-        let list_of_stacks: Vec<VecDeque<char>> = vec![
-            VecDeque::from(['Z', 'N']),
-            VecDeque::from(['M', 'C', 'D']),
-            VecDeque::from(['P']),
-        ];
+    fn test_plan_moves_with_two_stacks() {
+        let start = CrateYard::new(vec![vec!['A', 'B'], vec![]]);
+        let target = CrateYard::new(vec![vec![], vec!['A', 'B']]);
 
-        let expected = "NDP".to_string();
+        assert!(plan_moves(&start, &start, &CrateMover9001)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            plan_moves(&start, &target, &CrateMover9001).unwrap(),
+            [Move {
+                count: 2,
+                from: 0,
+                to: 1
+            }]
+        );
+        assert!(plan_moves(&start, &target, &CrateMover9000).is_err());
+    }
 
-        let output = get_last_crate_of_each_stack(list_of_stacks).unwrap();
+    #[test]
+    fn test_plan_runners() {
+        let input = format!("{}\n{}", DRAWING, MOVES);
+
+        let part1_input = part1_plan(&input);
+        let part2_input = part2_plan(&input);
+
+        assert!(part1_input.starts_with(&format!("\n{}\n", DRAWING)));
+        assert_eq!(part1(&part1_input[1..]), part1(&input));
+        assert_eq!(part2(&part2_input[1..]), part2(&input));
+    }
+
+    #[test]
+    fn test_tracked_simulation() {
+        let simulation =
+            TrackedSimulation::run(&example_yard(), &example_moves(), &CrateMover9000).unwrap();
+
+        let d = simulation.from_origin(1, 2).unwrap();
+        assert_eq!((d.label, d.position, d.moves), ('D', (2, 1), 2));
+        let m = simulation.from_origin(1, 0).unwrap();
+        assert_eq!((m.label, m.position, m.moves), ('M', (1, 0), 2));
+        assert_eq!(simulation.at_position(2, 3).unwrap().origin, (0, 0));
+        assert_eq!(simulation.at_position(2, 4), None);
+        assert_eq!(simulation.from_origin(2, 1), None);
+
+        assert_eq!(simulation.max_heights(), [3, 3, 4]);
+        assert_eq!(simulation.tallest_stack(), Some((2, 4)));
+        assert_eq!(simulation.most_moved().unwrap().origin, (1, 0));
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(7)]
+    fn test_tracked_simulation_with_repeated_labels(#[case] seed: u64) {
+        let generator = InputGenerator {
+            alphabet: vec!['A', 'B'],
+            seed,
+            ..InputGenerator::default()
+        };
+        let (yard, moves) = generator.generate().unwrap();
+        let crane = CappedCrane::new(3).unwrap();
+
+        let simulation = TrackedSimulation::run(&yard, &moves, &crane).unwrap();
+        let mut expected = yard.clone();
+        expected.apply_all(&moves, &crane).unwrap();
+
+        assert_eq!(simulation.yard(), expected);
+        assert_eq!(simulation.crates().len(), 9 * 8);
+        let moved: usize = simulation.crates().iter().map(|c| c.moves).sum();
+        let count: usize = moves.iter().map(|m| m.movement.count).sum();
+        assert_eq!(moved, count);
+    }
+
+    #[test]
+    fn test_tracked_simulation_fail() {
+        let moves = parse_moves("move 1 from 2 to 1\nmove 4 from 1 to 3\n", 1).unwrap();
+
+        let output = TrackedSimulation::run(&example_yard(), &moves, &CrateMover9000);
+
+        assert_eq!(
+            output.unwrap_err().to_string(),
+            "Line 2 'move 4 from 1 to 3': Can't move 4 crates from stack 1, it only has 3. \
+             Stack sizes: 3, 2, 1."
+        );
+    }
+
+    #[rstest]
+    #[case(0, None)]
+    #[case(1, None)]
+    #[case(0xDEAD_BEEF, None)]
+    #[case(0, Some(1))]
+    #[case(1, Some(4))]
+    fn test_input_generator(#[case] seed: u64, #[case] max_count: Option<usize>) {
+        let generator = InputGenerator {
+            stacks: 12,
+            height: 5,
+            alphabet: "ABC".chars().collect(),
+            moves: 200,
+            max_count,
+            seed,
+        };
+
+        let (yard, moves) = generator.generate().unwrap();
+        let input = generator.generate_input().unwrap();
+
+        assert_eq!(parse_input(&input).unwrap(), (yard.clone(), moves.clone()));
+        assert_eq!(generator.generate_input().unwrap(), input);
+        assert!(moves
+            .iter()
+            .all(|instruction| instruction.movement.from != instruction.movement.to));
+        assert!(yard.stacks().iter().flatten().all(|c| "ABC".contains(*c)));
+        assert!(moves
+            .iter()
+            .all(|instruction| max_count.is_none_or(|max| instruction.movement.count <= max)));
+
+        let mut capped = yard.clone();
+        capped
+            .apply_all(&moves, &CappedCrane::new(2).unwrap())
+            .unwrap();
+        assert!(get_top_crates_after_moves(&input, &CrateMover9000).is_ok());
+        assert!(get_top_crates_after_moves(&input, &CrateMover9001).is_ok());
+    }
+
+    #[test]
+    fn test_input_generator_seeds() {
+        let generator = InputGenerator::default();
+        let other_seed = InputGenerator {
+            seed: 2023,
+            ..InputGenerator::default()
+        };
+
+        assert_ne!(
+            generator.generate_input().unwrap(),
+            other_seed.generate_input().unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(0, 5, "AB", 0, None)]
+    #[case(1, 5, "AB", 1, None)]
+    #[case(3, 0, "AB", 1, None)]
+    #[case(3, 5, "", 1, None)]
+    #[case(3, 5, "A B", 1, None)]
+    #[case(3, 5, "AB", 1, Some(0))]
+    fn test_input_generator_fail(
+        #[case] stacks: usize,
+        #[case] height: usize,
+        #[case] alphabet: &str,
+        #[case] moves: usize,
+        #[case] max_count: Option<usize>,
+    ) {
+        let generator = InputGenerator {
+            stacks,
+            height,
+            alphabet: alphabet.chars().collect(),
+            moves,
+            max_count,
+            seed: 0,
+        };
+
+        assert!(generator.generate().is_err());
+    }
+
+    #[test]
+    fn test_capped_crane_fail() {
+        assert!(CappedCrane::new(0).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        "move 3 from 3 to 1",
+        "Can't move 3 crates from stack 3, it only has 1. Stack sizes: 2, 3, 1."
+    )]
+    #[case(
+        "move 1 from 4 to 1",
+        "There is no stack 4, the yard has 3 stacks. Stack sizes: 2, 3, 1."
+    )]
+    #[case(
+        "move 1 from 1 to 4",
+        "There is no stack 4, the yard has 3 stacks. Stack sizes: 2, 3, 1."
+    )]
+    #[case(
+        "move 1 from 2 to 2",
+        "Can't move crates from stack 2 to itself. Stack sizes: 2, 3, 1."
+    )]
+    fn test_apply_fail(#[case] line: &str, #[case] expected: &str) {
+        let instruction: Move = line.parse().unwrap();
+
+        let output = example_yard().apply(&instruction, &CrateMover9000);
+
+        assert_eq!(output.unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_apply_all_fail() {
+        let moves = parse_moves(
+            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 3 from 1 to 2\n",
+            1,
+        );
+
+        let output = example_yard().apply_all(&moves.unwrap(), &CrateMover9001);
+
+        assert_eq!(
+            output.unwrap_err().to_string(),
+            "Line 3 'move 3 from 1 to 2': Can't move 3 crates from stack 1, it only has 0. \
+             Stack sizes: 0, 2, 4."
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "move 1 from 0 to 1\n",
+        "Line 1 'move 1 from 0 to 1': Stacks are numbered from 1, found stack 0."
+    )]
+    #[case(
+        "move 1 from 1 to 2\nmove 1 to 2\n",
+        "Line 2 'move 1 to 2': Couldn't parse instruction groups."
+    )]
+    #[case(
+        "move 1 from 1 to 2\n\nmove 1 to 2\n",
+        "Line 3 'move 1 to 2': Couldn't parse instruction groups."
+    )]
+    fn test_parse_moves_fail(#[case] moves: &str, #[case] expected: &str) {
+        assert_eq!(parse_moves(moves, 1).unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_errors_give_the_input_line() {
+        let input = format!("{}\nmove 1 from 2 to 1\n\nmove 9 from 1 to 3\n", DRAWING);
+        let expected = "Line 8 'move 9 from 1 to 3': Can't move 9 crates from stack 1, it only \
+                        has 3. Stack sizes: 3, 2, 1.";
+
+        assert_eq!(part1(&input), expected);
+        assert_eq!(
+            step_through(&input, &CrateMover9000)
+                .unwrap_err()
+                .to_string(),
+            expected
+        );
+        let (yard, moves) = parse_input(&input).unwrap();
+        let output = TrackedSimulation::run(&yard, &moves, &CrateMover9000);
+        assert_eq!(output.unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_top_crates() {
+        let yard = CrateYard::new(vec![vec!['Z', 'N'], vec![], vec!['P']]);
 
-        assert_eq!(output, expected);
+        assert_eq!(yard.top_crates(), "NP");
     }
 }
@@ -0,0 +1,223 @@
+use eyre::{eyre, Report, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// An inclusive range of sections, like `2-4` (sections 2, 3 and 4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    start: u32,
+    end: u32,
+}
+
+/// A set of sections, stored as sorted intervals that don't overlap nor touch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl Interval {
+    pub fn new(start: u32, end: u32) -> Result<Self> {
+        if start > end {
+            return Err(eyre!("Range '{}-{}' starts after it ends", start, end));
+        }
+
+        Ok(Interval { start, end })
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+
+    /// Number of sections in the interval, never 0.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        u64::from(self.end - self.start) + 1
+    }
+
+    /// Whether every section of `other` is in this interval.
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.start <= other.start && self.end >= other.end
+    }
+
+    /// Whether both intervals share at least one section.
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Whether both intervals are disjoint but there are no sections in between.
+    pub fn is_adjacent(&self, other: &Interval) -> bool {
+        self.end.checked_add(1) == Some(other.start) || other.end.checked_add(1) == Some(self.start)
+    }
+
+    /// The sections shared by both intervals, if any.
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        if self.overlaps(other) {
+            Some(Interval {
+                start: self.start.max(other.start),
+                end: self.end.min(other.end),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The sections in either interval.
+    pub fn union(&self, other: &Interval) -> IntervalSet {
+        [*self, *other].into_iter().collect()
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| eyre!("Expected a range like 'start-end', got '{}'", s))?;
+
+        Interval::new(parse_section(start)?, parse_section(end)?)
+    }
+}
+
+fn parse_section(section: &str) -> Result<u32> {
+    section
+        .parse::<u32>()
+        .map_err(|e| eyre!("Invalid section '{}': {}", section, e))
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the sections of the interval, merging it with the ones it overlaps or touches.
+    pub fn insert(&mut self, interval: Interval) {
+        let first = self
+            .intervals
+            .partition_point(|i| i.end < interval.start && !i.is_adjacent(&interval));
+        let last = self
+            .intervals
+            .partition_point(|i| i.start <= interval.end || i.is_adjacent(&interval));
+
+        let merged = self.intervals[first..last]
+            .iter()
+            .fold(interval, |merged, i| Interval {
+                start: merged.start.min(i.start),
+                end: merged.end.max(i.end),
+            });
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Number of sections in the set.
+    pub fn len(&self) -> u64 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Whether every section of the interval is in the set.
+    pub fn contains(&self, interval: &Interval) -> bool {
+        self.intervals.iter().any(|i| i.contains(interval))
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for interval in iter {
+            set.insert(interval);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn interval(s: &str) -> Interval {
+        s.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("2-8", "3-7", true, true, Some("3-7"))]
+    #[case("3-7", "2-8", false, true, Some("3-7"))]
+    #[case("5-7", "7-9", false, true, Some("7-7"))]
+    #[case("2-4", "6-8", false, false, None)]
+    #[case("2-4", "5-8", false, false, None)]
+    #[case("0-4294967295", "0-4294967295", true, true, Some("0-4294967295"))]
+    fn test_interval(
+        #[case] left: &str,
+        #[case] right: &str,
+        #[case] contains: bool,
+        #[case] overlaps: bool,
+        #[case] intersection: Option<&str>,
+    ) {
+        let (left, right) = (interval(left), interval(right));
+
+        assert_eq!(left.contains(&right), contains);
+        assert_eq!(left.overlaps(&right), overlaps);
+        assert_eq!(right.overlaps(&left), overlaps);
+        assert_eq!(left.intersection(&right), intersection.map(interval));
+    }
+
+    #[test]
+    fn test_full_width_len() {
+        let full = interval("0-4294967295");
+
+        assert_eq!(full.len(), 1 << 32);
+        assert_eq!(full.union(&full).len(), 1 << 32);
+    }
+
+    #[rstest]
+    #[case("2-4", "5-8", &["2-8"])]
+    #[case("2-4", "6-8", &["2-4", "6-8"])]
+    #[case("6-8", "2-4", &["2-4", "6-8"])]
+    #[case("2-6", "4-5", &["2-6"])]
+    fn test_interval_union(#[case] left: &str, #[case] right: &str, #[case] expected: &[&str]) {
+        let expected: Vec<Interval> = expected.iter().map(|s| interval(s)).collect();
+
+        assert_eq!(interval(left).union(&interval(right)).intervals(), expected);
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let set: IntervalSet = ["10-12", "1-2", "5-6", "3-3", "20-30", "11-19"]
+            .into_iter()
+            .map(interval)
+            .collect();
+
+        assert_eq!(
+            set.intervals(),
+            [interval("1-3"), interval("5-6"), interval("10-30")]
+        );
+        assert_eq!(set.len(), 3 + 2 + 21);
+        assert!(set.contains(&interval("12-25")));
+        assert!(!set.contains(&interval("3-5")));
+    }
+
+    #[rstest]
+    #[case("4-2")]
+    #[case("4")]
+    #[case("-1-2")]
+    fn test_interval_from_str_fail(#[case] input: &str) {
+        assert!(input.parse::<Interval>().is_err());
+    }
+}
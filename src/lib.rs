@@ -6,5 +6,6 @@ pub mod day3;
 pub mod day4;
 pub mod day5;
 pub mod day6;
+pub mod interval;
 
 aoc_lib! { year = 2022 }